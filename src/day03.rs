@@ -1,46 +1,66 @@
-use std::collections::HashMap;
 use std::fs::File;
 use std::io::BufReader;
 use std::io::prelude::*;
+use crate::options;
+use self::heatmap::Heatmap;
+use self::index::ClaimIndex;
 use self::rect::Rectangle;
+use self::sweep::{covered_area, difference_area, union_area};
 
+mod heatmap;
+mod index;
 mod rect;
+mod sweep;
 
-pub fn solve(input_file: File) {
+pub fn solve(input_file: File, options: &[String]) {
   let reader = BufReader::new(input_file);
 
   let claims: Vec<Rectangle> = reader
     .lines()
-    .flatten()
-    .map(|line| line.parse())
-    .flatten()
+    .map(Result::unwrap)
+    .enumerate()
+    .filter_map(|(index, line)| match line.parse() {
+      Ok(claim) => Some(claim),
+      Err(err) => {
+        eprintln!("Skipping line {}: {}", index + 1, err);
+        None
+      }
+    })
     .collect();
 
+  println!("Total claimed area: {}", union_area(&claims));
   println!("Total overlapping area: {}", overlapping_area(&claims));
-  println!("Nonoverlapping claim id: {}", find_nonoverlapping_claim(&claims).unwrap());
-}
 
-fn overlapping_area<T: AsRef<Rectangle>>(claims: &[T]) -> u32 {
-  let mut fabric: HashMap<(u32, u32), u32> = HashMap::new();
-  for rect in claims.iter() {
-    for point in rect.as_ref().points() {
-      *fabric.entry(point).or_insert(0) += 1;
-    }
-  }
-  fabric.values().filter(|&&n| n >= 2).count() as u32
-}
+  let conflicts = ClaimIndex::new(&claims).conflict_graph();
+  let nonoverlapping: Vec<String> = conflicts.isolated().iter().map(u32::to_string).collect();
+  println!("Nonoverlapping claim ids: {}", nonoverlapping.join(", "));
 
-fn find_nonoverlapping_claim<T: AsRef<Rectangle>>(claims: &[T]) -> Option<u32> {
-  'next: for (index, rect) in claims.iter().enumerate() {
-    for (other_index, other_rect) in claims.iter().enumerate() {
-      if index != other_index && rect.as_ref().overlaps(other_rect.as_ref()) {
-        continue 'next;
-      }
+  for option in options.iter() {
+    match options::split(option) {
+      ("conflicts", None) => print!("{}", conflicts.to_adjacency_list()),
+      ("conflicts", Some("dot")) => print!("{}", conflicts.to_dot()),
+      ("conflicts", Some(id)) => {
+        let id: u32 = id.parse().expect("--conflicts takes a claim id or `dot`");
+        let others: Vec<String> = conflicts.conflicts_of(id).iter().map(u32::to_string).collect();
+        println!("Claim {} conflicts with: {}", id, others.join(", "));
+
+        if let Some(claim) = claims.iter().find(|claim| claim.id == id) {
+          let rest: Vec<&Rectangle> = claims.iter().filter(|other| other.id != id).collect();
+          println!("Claim {} has {} square inches to itself", id, difference_area(&[claim], &rest));
+        }
+      },
+      ("heatmap", None) => print!("{}", Heatmap::new(&claims, &conflicts.isolated()).to_ascii()),
+      ("heatmap", Some(path)) => {
+        let mut file = File::create(path).expect("couldn't create heatmap file");
+        Heatmap::new(&claims, &conflicts.isolated()).write_pgm(&mut file).unwrap();
+      },
+      _ => panic!("Unknown option for day 3: {}", option)
     }
-    return Some(rect.as_ref().id);
   }
+}
 
-  None
+fn overlapping_area<T: AsRef<Rectangle>>(claims: &[T]) -> u64 {
+  covered_area(claims, 2)
 }
 
 #[cfg(test)]
@@ -60,14 +80,14 @@ mod tests {
   }
 
   #[test]
-  fn test_find_nonoverlapping_claim() {
+  fn test_find_nonoverlapping_claims() {
     assert_eq!(
-      Some(3),
-      find_nonoverlapping_claim(&[
+      vec![3],
+      ClaimIndex::new(&[
         Rectangle { id: 1, x: 1, y: 3, w: 4, h: 4 },
         Rectangle { id: 2, x: 3, y: 1, w: 4, h: 4 },
         Rectangle { id: 3, x: 5, y: 5, w: 2, h: 2 },
-      ])
+      ]).conflict_graph().isolated()
     );
   }
 }
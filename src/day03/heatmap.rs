@@ -0,0 +1,144 @@
+use std::io;
+use std::io::prelude::*;
+use super::rect::Rectangle;
+
+const DENSITY: &[u8] = b".123456789";
+const OVERFLOW: char = '+';
+const HIGHLIGHT: char = '#';
+
+/// How many claims cover each square inch of the fabric, from its top left
+/// corner to the furthest edge of any claim.
+pub struct Heatmap {
+  width: u32,
+  height: u32,
+  counts: Vec<u32>,
+  highlighted: Vec<bool>
+}
+
+impl Heatmap {
+  /// Builds the heatmap for `claims`, marking the squares of the claims whose
+  /// ids are in `highlight`.
+  pub fn new<T: AsRef<Rectangle>>(claims: &[T], highlight: &[u32]) -> Self {
+    let origin = Rectangle { id: 0, x: 0, y: 0, w: 1, h: 1 };
+    let fabric = claims.iter()
+      .try_fold(origin, |fabric, rect| fabric.bounding_box(rect.as_ref()))
+      .expect("fabric is too big to render");
+
+    let size = fabric.area() as usize;
+    let mut heatmap = Heatmap {
+      width: fabric.w,
+      height: fabric.h,
+      counts: vec![0; size],
+      highlighted: vec![false; size]
+    };
+
+    for rect in claims.iter().map(AsRef::as_ref) {
+      let highlighted = highlight.contains(&rect.id);
+      for point in rect.points() {
+        let index = heatmap.index(point);
+        heatmap.counts[index] += 1;
+        heatmap.highlighted[index] |= highlighted;
+      }
+    }
+
+    heatmap
+  }
+
+  fn index(&self, (x, y): (u32, u32)) -> usize {
+    y as usize * self.width as usize + x as usize
+  }
+
+  pub fn count_at(&self, point: (u32, u32)) -> u32 {
+    self.counts[self.index(point)]
+  }
+
+  /// Renders one character per square inch: `.` for unclaimed fabric, the
+  /// number of claims up to 9, `+` for more than that, and `#` for
+  /// highlighted claims.
+  pub fn to_ascii(&self) -> String {
+    let mut ascii = String::with_capacity(self.counts.len() + self.height as usize);
+
+    for y in 0..self.height {
+      for x in 0..self.width {
+        ascii.push(if self.highlighted[self.index((x, y))] {
+          HIGHLIGHT
+        } else {
+          DENSITY.get(self.count_at((x, y)) as usize).map_or(OVERFLOW, |&ch| ch as char)
+        });
+      }
+      ascii.push('\n');
+    }
+
+    ascii
+  }
+
+  /// Writes a binary PGM image. Unclaimed fabric is black, claimed squares
+  /// get brighter the more claims cover them, and highlighted claims are the
+  /// only pure white squares.
+  pub fn write_pgm<W: Write>(&self, out: &mut W) -> io::Result<()> {
+    let max_count = self.counts.iter().cloned().max().unwrap_or(0).max(1);
+
+    write!(out, "P5\n{} {}\n255\n", self.width, self.height)?;
+
+    let pixels: Vec<u8> = self.counts.iter()
+      .zip(self.highlighted.iter())
+      .map(|(&count, &highlighted)| match (count, highlighted) {
+        (_, true) => 255,
+        (0, false) => 0,
+        (count, false) => (63 + 191 * (count - 1) / max_count) as u8
+      })
+      .collect();
+
+    out.write_all(&pixels)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn claims() -> Vec<Rectangle> {
+    vec![
+      Rectangle { id: 1, x: 1, y: 3, w: 4, h: 4 },
+      Rectangle { id: 2, x: 3, y: 1, w: 4, h: 4 },
+      Rectangle { id: 3, x: 5, y: 5, w: 2, h: 2 },
+    ]
+  }
+
+  #[test]
+  fn test_count_at() {
+    let heatmap = Heatmap::new(&claims(), &[]);
+
+    assert_eq!(heatmap.count_at((0, 0)), 0);
+    assert_eq!(heatmap.count_at((1, 3)), 1);
+    assert_eq!(heatmap.count_at((4, 4)), 2);
+    assert_eq!(heatmap.count_at((6, 6)), 1);
+  }
+
+  #[test]
+  fn test_to_ascii() {
+    let heatmap = Heatmap::new(&claims(), &[3]);
+
+    assert_eq!(
+      heatmap.to_ascii(),
+      "\
+.......
+...1111
+...1111
+.112211
+.112211
+.1111##
+.1111##
+"
+    );
+  }
+
+  #[test]
+  fn test_write_pgm() {
+    let heatmap = Heatmap::new(&[Rectangle { id: 1, x: 1, y: 0, w: 1, h: 1 }], &[]);
+    let mut pgm = Vec::new();
+    heatmap.write_pgm(&mut pgm).unwrap();
+
+    assert_eq!(pgm, b"P5\n2 1\n255\n\x00\x3f".to_vec());
+  }
+}
@@ -0,0 +1,257 @@
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use super::rect::Rectangle;
+
+// Claims covering more cells than this are kept out of the grid and checked
+// one by one instead.
+const MAX_CELLS_PER_CLAIM: u64 = 1024;
+
+/// Buckets claims into a uniform grid so that finding the claims that might
+/// overlap a given rectangle only has to look at the cells it covers.
+pub struct ClaimIndex<'a> {
+  claims: Vec<&'a Rectangle>,
+  cell_size: u64,
+  cells: HashMap<(u64, u64), Vec<usize>>,
+  oversized: Vec<usize>
+}
+
+impl<'a> ClaimIndex<'a> {
+  pub fn new<T: AsRef<Rectangle>>(claims: &'a [T]) -> Self {
+    let claims: Vec<&Rectangle> = claims.iter()
+      .map(AsRef::as_ref)
+      .filter(|rect| !rect.is_empty())
+      .collect();
+
+    // Cells about the size of a typical claim keep both the number of cells
+    // per claim and the number of claims per cell small. The median keeps a
+    // few huge claims from blowing the cells up for everyone else.
+    let mut sides: Vec<u64> = claims.iter()
+      .map(|rect| u64::from(rect.w.max(rect.h)))
+      .collect();
+    sides.sort();
+    let cell_size = sides.get(sides.len() / 2).cloned().unwrap_or(1).max(1);
+
+    let mut index = ClaimIndex { claims, cell_size, cells: HashMap::new(), oversized: Vec::new() };
+
+    for i in 0..index.claims.len() {
+      if index.cell_count(index.claims[i]) > MAX_CELLS_PER_CLAIM {
+        index.oversized.push(i);
+        continue;
+      }
+      for cell in index.cells_covered_by(index.claims[i]) {
+        index.cells.entry(cell).or_default().push(i);
+      }
+    }
+
+    index
+  }
+
+  // The columns and rows of cells a non-empty rectangle covers.
+  fn cell_range(&self, rect: &Rectangle) -> ((u64, u64), (u64, u64)) {
+    let left = u64::from(rect.x) / self.cell_size;
+    let right = (rect.x_end() - 1) / self.cell_size;
+    let top = u64::from(rect.y) / self.cell_size;
+    let bottom = (rect.y_end() - 1) / self.cell_size;
+
+    ((left, right), (top, bottom))
+  }
+
+  fn cell_count(&self, rect: &Rectangle) -> u64 {
+    let ((left, right), (top, bottom)) = self.cell_range(rect);
+    (right - left + 1).saturating_mul(bottom - top + 1)
+  }
+
+  fn cells_covered_by(&self, rect: &Rectangle) -> Vec<(u64, u64)> {
+    let ((left, right), (top, bottom)) = self.cell_range(rect);
+
+    (top..=bottom)
+      .flat_map(|y| (left..=right).map(move |x| (x, y)))
+      .collect()
+  }
+
+  /// Returns the claims overlapping `rect`, in the order they were indexed.
+  pub fn overlapping(&self, rect: &Rectangle) -> Vec<&'a Rectangle> {
+    if rect.is_empty() {
+      return vec![];
+    }
+
+    // A query bigger than the grid is worth walking is cheaper to answer by
+    // checking every claim.
+    let mut candidates: Vec<usize> = if self.cell_count(rect) > MAX_CELLS_PER_CLAIM {
+      (0..self.claims.len()).collect()
+    } else {
+      self.cells_covered_by(rect).iter()
+        .filter_map(|cell| self.cells.get(cell))
+        .flatten()
+        .chain(self.oversized.iter())
+        .cloned()
+        .collect()
+    };
+    candidates.sort();
+    candidates.dedup();
+
+    candidates.into_iter()
+      .map(|i| self.claims[i])
+      .filter(|other| other.overlaps(rect))
+      .collect()
+  }
+
+  pub fn conflict_graph(&self) -> ConflictGraph {
+    let mut conflicts: BTreeMap<u32, Vec<u32>> = BTreeMap::new();
+
+    for &rect in self.claims.iter() {
+      let others = self.overlapping(rect).into_iter()
+        .filter(|&other| !std::ptr::eq(other, rect))
+        .map(|other| other.id);
+
+      let entry = conflicts.entry(rect.id).or_default();
+      entry.extend(others);
+      entry.sort();
+      entry.dedup();
+    }
+
+    ConflictGraph { conflicts }
+  }
+}
+
+/// Which claims collide with which, keyed by claim id.
+#[derive(Debug, PartialEq)]
+pub struct ConflictGraph {
+  conflicts: BTreeMap<u32, Vec<u32>>
+}
+
+impl ConflictGraph {
+  pub fn conflicts_of(&self, id: u32) -> &[u32] {
+    self.conflicts.get(&id).map_or(&[], Vec::as_slice)
+  }
+
+  pub fn isolated(&self) -> Vec<u32> {
+    self.conflicts.iter()
+      .filter(|(_, others)| others.is_empty())
+      .map(|(&id, _)| id)
+      .collect()
+  }
+
+  /// One line per claim: its id, a colon, then the ids it conflicts with.
+  pub fn to_adjacency_list(&self) -> String {
+    self.conflicts.iter()
+      .map(|(id, others)| {
+        let others: Vec<String> = others.iter().map(u32::to_string).collect();
+        format!("{}: {}\n", id, others.join(" "))
+      })
+      .collect()
+  }
+
+  pub fn to_dot(&self) -> String {
+    let mut dot = String::from("graph conflicts {\n");
+
+    for (&id, others) in self.conflicts.iter() {
+      if others.is_empty() {
+        dot.push_str(&format!("  {};\n", id));
+      }
+      for &other in others.iter().filter(|&&other| other > id) {
+        dot.push_str(&format!("  {} -- {};\n", id, other));
+      }
+    }
+
+    dot.push_str("}\n");
+    dot
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn claims() -> Vec<Rectangle> {
+    vec![
+      Rectangle { id: 1, x: 1, y: 3, w: 4, h: 4 },
+      Rectangle { id: 2, x: 3, y: 1, w: 4, h: 4 },
+      Rectangle { id: 3, x: 5, y: 5, w: 2, h: 2 },
+      Rectangle { id: 4, x: 20, y: 20, w: 40, h: 40 },
+      Rectangle { id: 5, x: 59, y: 59, w: 1, h: 1 },
+    ]
+  }
+
+  #[test]
+  fn test_overlapping() {
+    let claims = claims();
+    let index = ClaimIndex::new(&claims);
+
+    assert_eq!(
+      index.overlapping(&Rectangle { id: 0, x: 4, y: 4, w: 1, h: 1 }),
+      vec![&claims[0], &claims[1]]
+    );
+    assert_eq!(
+      index.overlapping(&Rectangle { id: 0, x: 0, y: 0, w: 100, h: 100 }).len(),
+      5
+    );
+    assert!(index.overlapping(&Rectangle { id: 0, x: 7, y: 0, w: 10, h: 10 }).is_empty());
+  }
+
+  #[test]
+  fn test_conflict_graph() {
+    let claims = claims();
+    let graph = ClaimIndex::new(&claims).conflict_graph();
+
+    assert_eq!(graph.conflicts_of(1), &[2]);
+    assert_eq!(graph.conflicts_of(2), &[1]);
+    assert_eq!(graph.conflicts_of(3), &[] as &[u32]);
+    assert_eq!(graph.conflicts_of(4), &[5]);
+    assert_eq!(graph.isolated(), vec![3]);
+  }
+
+  #[test]
+  fn test_conflict_graph_ignores_empty_claims() {
+    let claims = vec![
+      Rectangle { id: 1, x: 1, y: 1, w: 3, h: 3 },
+      Rectangle { id: 2, x: 2, y: 2, w: 0, h: 0 },
+    ];
+    let graph = ClaimIndex::new(&claims).conflict_graph();
+
+    assert_eq!(graph.isolated(), vec![1]);
+  }
+
+  #[test]
+  fn test_oversized_claims() {
+    let mut claims: Vec<Rectangle> = (0..100)
+      .map(|i| Rectangle { id: i + 1, x: i * 10, y: i * 10, w: 5, h: 5 })
+      .collect();
+    claims.push(Rectangle { id: 1000, x: 3, y: 3, w: u32::MAX - 3, h: u32::MAX - 3 });
+    let index = ClaimIndex::new(&claims);
+
+    assert_eq!(index.cell_size, 5);
+    assert_eq!(index.oversized, vec![100]);
+    assert_eq!(
+      index.overlapping(&Rectangle { id: 0, x: 0, y: 0, w: 4, h: 4 }),
+      vec![&claims[0], &claims[100]]
+    );
+    assert_eq!(
+      index.overlapping(&Rectangle { id: 0, x: 0, y: 0, w: u32::MAX, h: u32::MAX }).len(),
+      101
+    );
+
+    let graph = index.conflict_graph();
+    assert_eq!(graph.conflicts_of(1000).len(), 100);
+    assert_eq!(graph.conflicts_of(50), &[1000]);
+  }
+
+  #[test]
+  fn test_to_adjacency_list() {
+    let claims = claims();
+    let graph = ClaimIndex::new(&claims).conflict_graph();
+
+    assert_eq!(graph.to_adjacency_list(), "1: 2\n2: 1\n3: \n4: 5\n5: 4\n");
+  }
+
+  #[test]
+  fn test_to_dot() {
+    let claims = claims();
+    let graph = ClaimIndex::new(&claims).conflict_graph();
+
+    assert_eq!(
+      graph.to_dot(),
+      "graph conflicts {\n  1 -- 2;\n  3;\n  4 -- 5;\n}\n"
+    );
+  }
+}
@@ -1,7 +1,8 @@
-use std::num::ParseIntError;
+use std::error::Error;
+use std::fmt;
 use std::str::FromStr;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub struct Rectangle {
   pub id: u32,
   pub x: u32,
@@ -19,55 +20,168 @@ impl<'a> Iterator for Points<'a> {
   type Item = (u32, u32);
 
   fn next(&mut self) -> Option<(u32, u32)> {
-    let (mut next_x, mut next_y) = match self.last_point {
-      Some((x, y)) => (x + 1, y),
-      None => (self.rectangle.x, self.rectangle.y),
+    let (right, bottom) = match (self.rectangle.right(), self.rectangle.bottom()) {
+      (Some(right), Some(bottom)) => (right, bottom),
+      _ => return None
     };
 
-    if next_x > self.rectangle.right() {
-      next_x = self.rectangle.x;
-      next_y += 1;
-    }
-
-    if next_y > self.rectangle.bottom() {
-      self.last_point = None;
-    } else {
-      self.last_point = Some((next_x, next_y));
-    }
+    self.last_point = match self.last_point {
+      None => Some((self.rectangle.x, self.rectangle.y)),
+      Some((x, y)) if x < right => Some((x + 1, y)),
+      Some((_, y)) if y < bottom => Some((self.rectangle.x, y + 1)),
+      Some(_) => None
+    };
 
     self.last_point
   }
 }
 
 impl Rectangle {
-  pub fn points(&self) -> Points {
+  pub fn points(&self) -> Points<'_> {
     Points { rectangle: self, last_point: None }
   }
 
+  pub fn is_empty(&self) -> bool {
+    self.w == 0 || self.h == 0
+  }
+
+  pub fn area(&self) -> u64 {
+    u64::from(self.w) * u64::from(self.h)
+  }
+
+  #[allow(dead_code)]
   pub fn top(&self) -> u32 {
     self.y
   }
 
-  pub fn bottom(&self) -> u32 {
-    self.y + self.h - 1
+  /// The last row inside the rectangle, or `None` if the rectangle is empty
+  /// or reaches past `u32::MAX`.
+  pub fn bottom(&self) -> Option<u32> {
+    self.y.checked_add(self.h.checked_sub(1)?)
   }
 
+  #[allow(dead_code)]
   pub fn left(&self) -> u32 {
     self.x
   }
 
-  pub fn right(&self) -> u32 {
-    self.x + self.w - 1
+  /// The last column inside the rectangle, or `None` if the rectangle is
+  /// empty or reaches past `u32::MAX`.
+  pub fn right(&self) -> Option<u32> {
+    self.x.checked_add(self.w.checked_sub(1)?)
+  }
+
+  /// The first column past the rectangle. Unlike `right`, this can't overflow
+  /// and is defined for empty rectangles.
+  pub fn x_end(&self) -> u64 {
+    u64::from(self.x) + u64::from(self.w)
+  }
+
+  /// The first row past the rectangle.
+  pub fn y_end(&self) -> u64 {
+    u64::from(self.y) + u64::from(self.h)
+  }
+
+  // Builds a rectangle from its edges, or `None` if it would be empty.
+  fn from_edges(id: u32, left: u64, top: u64, right_end: u64, bottom_end: u64) -> Option<Rectangle> {
+    if right_end <= left || bottom_end <= top {
+      return None;
+    }
+
+    Some(Rectangle {
+      id,
+      x: left as u32,
+      y: top as u32,
+      w: (right_end - left) as u32,
+      h: (bottom_end - top) as u32
+    })
   }
 
   pub fn overlaps(&self, other: &Rectangle) -> bool {
-    !(
-      other.right() < self.left() ||
-      other.bottom() < self.top() ||
-      other.left() > self.right() ||
-      other.top() > self.bottom()
+    self.intersection(other).is_some()
+  }
+
+  /// The area shared by both rectangles, keeping this rectangle's id, or
+  /// `None` if they don't overlap.
+  pub fn intersection(&self, other: &Rectangle) -> Option<Rectangle> {
+    Rectangle::from_edges(
+      self.id,
+      u64::from(self.x.max(other.x)),
+      u64::from(self.y.max(other.y)),
+      self.x_end().min(other.x_end()),
+      self.y_end().min(other.y_end())
     )
   }
+
+  /// The smallest rectangle containing both rectangles, keeping this
+  /// rectangle's id. Empty rectangles are ignored. Returns `None` if the
+  /// result is too big to fit in a `Rectangle`.
+  pub fn bounding_box(&self, other: &Rectangle) -> Option<Rectangle> {
+    match (self.is_empty(), other.is_empty()) {
+      (_, true) => return Some(*self),
+      (true, false) => return Some(Rectangle { id: self.id, ..*other }),
+      _ => {}
+    }
+
+    let left = self.x.min(other.x);
+    let top = self.y.min(other.y);
+    let w = self.x_end().max(other.x_end()) - u64::from(left);
+    let h = self.y_end().max(other.y_end()) - u64::from(top);
+
+    if w > u64::from(u32::MAX) || h > u64::from(u32::MAX) {
+      return None;
+    }
+
+    Some(Rectangle { id: self.id, x: left, y: top, w: w as u32, h: h as u32 })
+  }
+
+  /// Whether every square of `other` is inside this rectangle. Empty
+  /// rectangles contain nothing and aren't contained by anything.
+  #[allow(dead_code)]
+  pub fn contains(&self, other: &Rectangle) -> bool {
+    !self.is_empty() && !other.is_empty() &&
+      self.x <= other.x && other.x_end() <= self.x_end() &&
+      self.y <= other.y && other.y_end() <= self.y_end()
+  }
+
+  #[allow(dead_code)]
+  pub fn contains_point(&self, (x, y): (u32, u32)) -> bool {
+    self.x <= x && u64::from(x) < self.x_end() &&
+      self.y <= y && u64::from(y) < self.y_end()
+  }
+
+  /// The part of this rectangle inside `bounds`, which is empty if the two
+  /// don't overlap.
+  #[allow(dead_code)]
+  pub fn clip(&self, bounds: &Rectangle) -> Rectangle {
+    self.intersection(bounds)
+      .unwrap_or(Rectangle { id: self.id, x: self.x.max(bounds.x), y: self.y.max(bounds.y), w: 0, h: 0 })
+  }
+
+  /// The parts of this rectangle outside `other`, as up to four
+  /// non-overlapping rectangles with this rectangle's id.
+  #[allow(dead_code)]
+  pub fn difference(&self, other: &Rectangle) -> Vec<Rectangle> {
+    let inner = match self.intersection(other) {
+      Some(inner) => inner,
+      None if self.is_empty() => return vec![],
+      None => return vec![*self]
+    };
+
+    let (left, top) = (u64::from(self.x), u64::from(self.y));
+    let (inner_left, inner_top) = (u64::from(inner.x), u64::from(inner.y));
+
+    vec![
+      // Above, full width.
+      Rectangle::from_edges(self.id, left, top, self.x_end(), inner_top),
+      // Below, full width.
+      Rectangle::from_edges(self.id, left, inner.y_end(), self.x_end(), self.y_end()),
+      // Left of the intersection.
+      Rectangle::from_edges(self.id, left, inner_top, inner_left, inner.y_end()),
+      // Right of the intersection.
+      Rectangle::from_edges(self.id, inner.x_end(), inner_top, self.x_end(), inner.y_end())
+    ].into_iter().flatten().collect()
+  }
 }
 
 impl AsRef<Rectangle> for Rectangle {
@@ -76,23 +190,113 @@ impl AsRef<Rectangle> for Rectangle {
   }
 }
 
+impl fmt::Display for Rectangle {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "#{} @ {},{}: {}x{}", self.id, self.x, self.y, self.w, self.h)
+  }
+}
+
+/// Why a claim line didn't match `#id @ x,y: wxh`. Columns start at 1.
+#[derive(Debug, PartialEq)]
+pub enum ParseRectangleError {
+  Missing { expected: &'static str, column: usize },
+  InvalidNumber { field: &'static str, column: usize },
+  TrailingInput { column: usize }
+}
+
+impl fmt::Display for ParseRectangleError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      ParseRectangleError::Missing { expected, column } =>
+        write!(f, "expected {} at column {}", expected, column),
+      ParseRectangleError::InvalidNumber { field, column } =>
+        write!(f, "{} at column {} is too big", field, column),
+      ParseRectangleError::TrailingInput { column } =>
+        write!(f, "unexpected input at column {}", column)
+    }
+  }
+}
+
+impl Error for ParseRectangleError {}
+
+struct Cursor<'a> {
+  string: &'a str,
+  index: usize
+}
+
+impl<'a> Cursor<'a> {
+  fn column(&self) -> usize {
+    self.string[..self.index].chars().count() + 1
+  }
+
+  fn rest(&self) -> &'a str {
+    &self.string[self.index..]
+  }
+
+  fn skip_spaces(&mut self) {
+    let rest = self.rest();
+    self.index += rest.len() - rest.trim_start_matches(' ').len();
+  }
+
+  fn expect(&mut self, ch: char, expected: &'static str) -> Result<(), ParseRectangleError> {
+    if self.rest().starts_with(ch) {
+      self.index += ch.len_utf8();
+      Ok(())
+    } else {
+      Err(ParseRectangleError::Missing { expected, column: self.column() })
+    }
+  }
+
+  fn number(&mut self, field: &'static str) -> Result<u32, ParseRectangleError> {
+    let column = self.column();
+    let digits = self.rest().len() - self.rest().trim_start_matches(|c: char| c.is_ascii_digit()).len();
+
+    if digits == 0 {
+      return Err(ParseRectangleError::Missing { expected: field, column });
+    }
+
+    let number = self.rest()[..digits].parse()
+      .map_err(|_| ParseRectangleError::InvalidNumber { field, column })?;
+    self.index += digits;
+
+    Ok(number)
+  }
+}
+
 impl FromStr for Rectangle {
-  type Err = ParseIntError;
+  type Err = ParseRectangleError;
 
   fn from_str(string: &str) -> Result<Self, Self::Err> {
-    let nums: Vec<u32> = string
-      .split(|c: char| !c.is_numeric())
-      .filter(|n| !n.is_empty())
-      .map(|n| n.parse())
-      .collect::<Result<Vec<u32>, ParseIntError>>()?;
+    let mut cursor = Cursor { string, index: 0 };
+
+    cursor.skip_spaces();
+    cursor.expect('#', "`#`")?;
+    let id = cursor.number("claim id")?;
+    cursor.skip_spaces();
+    cursor.expect('@', "`@`")?;
+    cursor.skip_spaces();
+    let x = cursor.number("left edge")?;
+    cursor.expect(',', "`,`")?;
+    let y = cursor.number("top edge")?;
+    cursor.expect(':', "`:`")?;
+    cursor.skip_spaces();
+    let w = cursor.number("width")?;
+    cursor.expect('x', "`x`")?;
+    let h = cursor.number("height")?;
+    cursor.skip_spaces();
 
-    Ok(Rectangle { id: nums[0], x: nums[1], y: nums[2], w: nums[3], h: nums[4] })
+    if !cursor.rest().is_empty() {
+      return Err(ParseRectangleError::TrailingInput { column: cursor.column() });
+    }
+
+    Ok(Rectangle { id, x, y, w, h })
   }
 }
 
 #[cfg(test)]
 mod tests {
   use super::*;
+  use super::super::sweep::difference_area;
 
   #[test]
   fn test_rectangle_points() {
@@ -108,6 +312,158 @@ mod tests {
       Rectangle { id: 1, x: 3, y: 4, w: 0, h: 0 }.points().collect::<Vec<(u32, u32)>>(),
       vec![]
     );
+    assert_eq!(
+      Rectangle { id: 1, x: 0, y: 0, w: 0, h: 3 }.points().collect::<Vec<(u32, u32)>>(),
+      vec![]
+    );
+  }
+
+  #[test]
+  fn test_rectangle_edges() {
+    let rect = Rectangle { id: 1, x: 3, y: 4, w: 2, h: 3 };
+    assert_eq!((rect.left(), rect.top()), (3, 4));
+    assert_eq!(rect.right(), Some(4));
+    assert_eq!(rect.bottom(), Some(6));
+    assert_eq!(rect.x_end(), 5);
+    assert_eq!(rect.y_end(), 7);
+
+    let empty = Rectangle { id: 1, x: 0, y: 0, w: 0, h: 0 };
+    assert_eq!(empty.right(), None);
+    assert_eq!(empty.bottom(), None);
+
+    let edge = Rectangle { id: 1, x: u32::MAX, y: u32::MAX - 1, w: 1, h: 3 };
+    assert_eq!(edge.right(), Some(u32::MAX));
+    assert_eq!(edge.bottom(), None);
+    assert_eq!(edge.y_end(), u64::from(u32::MAX) + 2);
+  }
+
+  #[test]
+  fn test_rectangle_area() {
+    assert_eq!(Rectangle { id: 1, x: 3, y: 4, w: 2, h: 3 }.area(), 6);
+    assert_eq!(Rectangle { id: 1, x: 3, y: 4, w: 0, h: 3 }.area(), 0);
+    assert_eq!(
+      Rectangle { id: 1, x: 0, y: 0, w: u32::MAX, h: u32::MAX }.area(),
+      u64::from(u32::MAX) * u64::from(u32::MAX)
+    );
+  }
+
+  #[test]
+  fn test_rectangle_intersection() {
+    assert_eq!(
+      Rectangle { id: 1, x: 1, y: 3, w: 4, h: 4 }.intersection(
+        &Rectangle { id: 2, x: 3, y: 1, w: 4, h: 4 }
+      ),
+      Some(Rectangle { id: 1, x: 3, y: 3, w: 2, h: 2 })
+    );
+    assert_eq!(
+      Rectangle { id: 1, x: 1, y: 3, w: 4, h: 4 }.intersection(
+        &Rectangle { id: 3, x: 5, y: 5, w: 2, h: 2 }
+      ),
+      None
+    );
+    assert_eq!(
+      Rectangle { id: 1, x: 1, y: 1, w: 0, h: 0 }.intersection(
+        &Rectangle { id: 2, x: 0, y: 0, w: 5, h: 5 }
+      ),
+      None
+    );
+    assert_eq!(
+      Rectangle { id: 1, x: u32::MAX - 1, y: 0, w: 10, h: 1 }.intersection(
+        &Rectangle { id: 2, x: u32::MAX, y: 0, w: 10, h: 1 }
+      ),
+      Some(Rectangle { id: 1, x: u32::MAX, y: 0, w: 9, h: 1 })
+    );
+  }
+
+  #[test]
+  fn test_rectangle_bounding_box() {
+    assert_eq!(
+      Rectangle { id: 1, x: 1, y: 3, w: 4, h: 4 }.bounding_box(
+        &Rectangle { id: 3, x: 5, y: 5, w: 2, h: 2 }
+      ),
+      Some(Rectangle { id: 1, x: 1, y: 3, w: 6, h: 4 })
+    );
+    assert_eq!(
+      Rectangle { id: 1, x: 1, y: 3, w: 4, h: 4 }.bounding_box(
+        &Rectangle { id: 2, x: 100, y: 100, w: 0, h: 0 }
+      ),
+      Some(Rectangle { id: 1, x: 1, y: 3, w: 4, h: 4 })
+    );
+    assert_eq!(
+      Rectangle { id: 1, x: 0, y: 0, w: 1, h: 1 }.bounding_box(
+        &Rectangle { id: 2, x: u32::MAX, y: 0, w: 1, h: 1 }
+      ),
+      None
+    );
+  }
+
+  #[test]
+  fn test_rectangle_contains() {
+    let rect = Rectangle { id: 1, x: 1, y: 3, w: 4, h: 4 };
+
+    assert!(rect.contains(&Rectangle { id: 2, x: 2, y: 4, w: 3, h: 3 }));
+    assert!(rect.contains(&rect));
+    assert!(!rect.contains(&Rectangle { id: 2, x: 2, y: 4, w: 4, h: 3 }));
+    assert!(!rect.contains(&Rectangle { id: 2, x: 2, y: 4, w: 0, h: 0 }));
+
+    assert!(rect.contains_point((1, 3)));
+    assert!(rect.contains_point((4, 6)));
+    assert!(!rect.contains_point((5, 6)));
+    assert!(!rect.contains_point((0, 3)));
+  }
+
+  #[test]
+  fn test_rectangle_clip() {
+    let bounds = Rectangle { id: 0, x: 0, y: 0, w: 10, h: 10 };
+
+    assert_eq!(
+      Rectangle { id: 1, x: 8, y: 5, w: 4, h: 4 }.clip(&bounds),
+      Rectangle { id: 1, x: 8, y: 5, w: 2, h: 4 }
+    );
+    assert!(Rectangle { id: 1, x: 20, y: 5, w: 4, h: 4 }.clip(&bounds).is_empty());
+  }
+
+  #[test]
+  fn test_rectangle_difference() {
+    let rect = Rectangle { id: 1, x: 0, y: 0, w: 4, h: 4 };
+
+    assert_eq!(
+      rect.difference(&Rectangle { id: 2, x: 1, y: 1, w: 2, h: 2 }),
+      vec![
+        Rectangle { id: 1, x: 0, y: 0, w: 4, h: 1 },
+        Rectangle { id: 1, x: 0, y: 3, w: 4, h: 1 },
+        Rectangle { id: 1, x: 0, y: 1, w: 1, h: 2 },
+        Rectangle { id: 1, x: 3, y: 1, w: 1, h: 2 },
+      ]
+    );
+    assert_eq!(
+      rect.difference(&Rectangle { id: 2, x: 2, y: 0, w: 5, h: 5 }),
+      vec![Rectangle { id: 1, x: 0, y: 0, w: 2, h: 4 }]
+    );
+    assert_eq!(rect.difference(&rect), vec![]);
+    assert_eq!(
+      rect.difference(&Rectangle { id: 2, x: 10, y: 10, w: 1, h: 1 }),
+      vec![rect]
+    );
+  }
+
+  #[test]
+  fn test_rectangle_difference_matches_sweep() {
+    let rects = [
+      Rectangle { id: 1, x: 0, y: 0, w: 4, h: 4 },
+      Rectangle { id: 2, x: 1, y: 1, w: 2, h: 2 },
+      Rectangle { id: 3, x: 2, y: 0, w: 5, h: 5 },
+      Rectangle { id: 4, x: 3, y: 3, w: 8, h: 1 },
+      Rectangle { id: 5, x: 10, y: 10, w: 1, h: 1 },
+      Rectangle { id: 6, x: 2, y: 2, w: 0, h: 3 },
+    ];
+
+    for rect in rects.iter() {
+      for other in rects.iter() {
+        let area: u64 = rect.difference(other).iter().map(Rectangle::area).sum();
+        assert_eq!(area, difference_area(&[rect], &[other]), "{} minus {}", rect, other);
+      }
+    }
   }
 
   #[test]
@@ -142,6 +498,16 @@ mod tests {
         &Rectangle { id: 2, x: 3, y: 4, w: 3, h: 3 }
       )
     );
+    assert!(
+      ! Rectangle { id: 1, x: 1, y: 2, w: 0, h: 0 }.overlaps(
+        &Rectangle { id: 2, x: 0, y: 0, w: 5, h: 5 }
+      )
+    );
+    assert!(
+      Rectangle { id: 1, x: u32::MAX - 1, y: 0, w: 5, h: 1 }.overlaps(
+        &Rectangle { id: 2, x: u32::MAX, y: 0, w: 5, h: 1 }
+      )
+    );
   }
 
   #[test]
@@ -154,5 +520,64 @@ mod tests {
       "#34 @ 36,23: 41x37".parse(),
       Ok(Rectangle { id: 34, x: 36, y: 23, w: 41, h: 37 })
     );
+    assert_eq!(
+      "  #2 @  3,1:   4x4  ".parse(),
+      Ok(Rectangle { id: 2, x: 3, y: 1, w: 4, h: 4 })
+    );
+  }
+
+  #[test]
+  fn test_rectangle_from_str_errors() {
+    assert_eq!(
+      "".parse::<Rectangle>(),
+      Err(ParseRectangleError::Missing { expected: "`#`", column: 1 })
+    );
+    assert_eq!(
+      "#1 @ 1,3".parse::<Rectangle>(),
+      Err(ParseRectangleError::Missing { expected: "`:`", column: 9 })
+    );
+    assert_eq!(
+      "#1 @ 1,3: 4x".parse::<Rectangle>(),
+      Err(ParseRectangleError::Missing { expected: "height", column: 13 })
+    );
+    assert_eq!(
+      "#1 @ 1,3 4x4".parse::<Rectangle>(),
+      Err(ParseRectangleError::Missing { expected: "`:`", column: 9 })
+    );
+    assert_eq!(
+      "#1 @ 1,3: 4x4 extra 9".parse::<Rectangle>(),
+      Err(ParseRectangleError::TrailingInput { column: 15 })
+    );
+    assert_eq!(
+      "#1 @ 1,-3: 4x4".parse::<Rectangle>(),
+      Err(ParseRectangleError::Missing { expected: "top edge", column: 8 })
+    );
+    assert_eq!(
+      "#1 @ 99999999999,3: 4x4".parse::<Rectangle>(),
+      Err(ParseRectangleError::InvalidNumber { field: "left edge", column: 6 })
+    );
+  }
+
+  #[test]
+  fn test_parse_rectangle_error_display() {
+    assert_eq!(
+      ParseRectangleError::Missing { expected: "width", column: 11 }.to_string(),
+      "expected width at column 11"
+    );
+    assert_eq!(
+      ParseRectangleError::InvalidNumber { field: "claim id", column: 2 }.to_string(),
+      "claim id at column 2 is too big"
+    );
+    assert_eq!(
+      ParseRectangleError::TrailingInput { column: 15 }.to_string(),
+      "unexpected input at column 15"
+    );
+  }
+
+  #[test]
+  fn test_rectangle_display() {
+    for line in &["#1 @ 1,3: 4x4", "#34 @ 36,23: 41x37", "#7 @ 0,0: 0x0"] {
+      assert_eq!(line.parse::<Rectangle>().unwrap().to_string(), *line);
+    }
   }
 }
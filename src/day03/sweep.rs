@@ -0,0 +1,229 @@
+use super::rect::Rectangle;
+
+#[derive(Debug)]
+struct Edge {
+  x: u64,
+  top: usize,
+  bottom: usize,
+  delta: isize
+}
+
+// Segment tree over the compressed y coordinates. Each node keeps how many
+// edges cover its whole span, plus, for every depth `j` up to `k`, how much of
+// its span is covered at least `j` times by the edges stored at or below it.
+struct CoverageTree {
+  ys: Vec<u64>,
+  k: usize,
+  count: Vec<isize>,
+  covered: Vec<u64>
+}
+
+impl CoverageTree {
+  pub fn new(ys: Vec<u64>, k: usize) -> Self {
+    let size = 4 * ys.len().max(1);
+    CoverageTree { ys, k, count: vec![0; size], covered: vec![0; size * (k + 1)] }
+  }
+
+  pub fn covered_length(&self) -> u64 {
+    // The root is node 1.
+    self.covered[(self.k + 1) + self.k]
+  }
+
+  pub fn update(&mut self, top: usize, bottom: usize, delta: isize) {
+    let leaves = self.ys.len() - 1;
+    self.update_aux(1, 0, leaves, top, bottom, delta);
+  }
+
+  fn update_aux(&mut self, node: usize, lo: usize, hi: usize, top: usize, bottom: usize, delta: isize) {
+    if bottom <= lo || hi <= top {
+      return;
+    }
+
+    if top <= lo && hi <= bottom {
+      self.count[node] += delta;
+    } else {
+      let mid = (lo + hi) / 2;
+      self.update_aux(2 * node, lo, mid, top, bottom, delta);
+      self.update_aux(2 * node + 1, mid, hi, top, bottom, delta);
+    }
+
+    self.recompute(node, lo, hi);
+  }
+
+  fn recompute(&mut self, node: usize, lo: usize, hi: usize) {
+    let span = self.ys[hi] - self.ys[lo];
+    let count = self.count[node] as usize;
+    let width = self.k + 1;
+
+    for j in 0..=self.k {
+      self.covered[node * width + j] = if count >= j {
+        span
+      } else if hi - lo == 1 {
+        0
+      } else {
+        self.covered[2 * node * width + j - count] +
+          self.covered[(2 * node + 1) * width + j - count]
+      };
+    }
+  }
+}
+
+/// Computes the number of square inches covered by at least `k` claims.
+///
+/// Sweeps a vertical line across the fabric, keeping a segment tree of the
+/// claims it currently crosses, so it runs in O(n log n) for a fixed `k`
+/// regardless of how large the claims are.
+pub fn covered_area<T: AsRef<Rectangle>>(claims: &[T], k: usize) -> u64 {
+  assert!(k >= 1, "k must be at least 1");
+
+  let claims: Vec<&Rectangle> = claims.iter()
+    .map(AsRef::as_ref)
+    .filter(|rect| !rect.is_empty())
+    .collect();
+
+  if claims.len() < k {
+    return 0;
+  }
+
+  let mut ys: Vec<u64> = claims.iter()
+    .flat_map(|rect| vec![u64::from(rect.y), rect.y_end()])
+    .collect();
+  ys.sort();
+  ys.dedup();
+
+  let y_index = |y: u64| ys.binary_search(&y).unwrap();
+
+  let mut edges: Vec<Edge> = Vec::with_capacity(claims.len() * 2);
+  for rect in claims.iter() {
+    let top = y_index(u64::from(rect.y));
+    let bottom = y_index(rect.y_end());
+    edges.push(Edge { x: u64::from(rect.x), top, bottom, delta: 1 });
+    edges.push(Edge { x: rect.x_end(), top, bottom, delta: -1 });
+  }
+  edges.sort_by_key(|edge| (edge.x, -edge.delta));
+
+  let mut tree = CoverageTree::new(ys, k);
+  let mut area = 0;
+  let mut last_x = edges[0].x;
+
+  for edge in edges.iter() {
+    area += tree.covered_length() * (edge.x - last_x);
+    tree.update(edge.top, edge.bottom, edge.delta);
+    last_x = edge.x;
+  }
+
+  area
+}
+
+/// The number of square inches covered by any of the claims.
+pub fn union_area<T: AsRef<Rectangle>>(claims: &[T]) -> u64 {
+  covered_area(claims, 1)
+}
+
+/// The number of square inches covered by `claims` but not by `others`.
+pub fn difference_area<T: AsRef<Rectangle>>(claims: &[T], others: &[T]) -> u64 {
+  let all: Vec<&Rectangle> = claims.iter().chain(others.iter()).map(AsRef::as_ref).collect();
+
+  union_area(&all) - union_area(others)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::collections::HashMap;
+
+  // The original implementation, which marks every square inch of every claim.
+  fn naive_covered_area(claims: &[Rectangle], k: usize) -> u64 {
+    let mut fabric: HashMap<(u32, u32), usize> = HashMap::new();
+    for rect in claims.iter() {
+      for point in rect.points() {
+        *fabric.entry(point).or_insert(0) += 1;
+      }
+    }
+    fabric.values().filter(|&&n| n >= k).count() as u64
+  }
+
+  fn pseudo_random_claims(n: u32, seed: u64) -> Vec<Rectangle> {
+    let mut state = seed;
+    let mut next = |max: u32| {
+      state = state.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1_442_695_040_888_963_407);
+      ((state >> 33) as u32) % max
+    };
+
+    (1..=n)
+      .map(|id| Rectangle { id, x: next(40), y: next(40), w: next(15) + 1, h: next(15) + 1 })
+      .collect()
+  }
+
+  #[test]
+  fn test_covered_area() {
+    let claims = [
+      Rectangle { id: 1, x: 1, y: 3, w: 4, h: 4 },
+      Rectangle { id: 2, x: 3, y: 1, w: 4, h: 4 },
+      Rectangle { id: 3, x: 5, y: 5, w: 2, h: 2 },
+    ];
+
+    assert_eq!(covered_area(&claims, 1), 32);
+    assert_eq!(covered_area(&claims, 2), 4);
+    assert_eq!(covered_area(&claims, 3), 0);
+  }
+
+  #[test]
+  fn test_covered_area_ignores_empty_claims() {
+    let claims = [
+      Rectangle { id: 1, x: 1, y: 1, w: 0, h: 5 },
+      Rectangle { id: 2, x: 1, y: 1, w: 5, h: 0 },
+      Rectangle { id: 3, x: 1, y: 1, w: 2, h: 2 },
+    ];
+
+    assert_eq!(covered_area(&claims, 1), 4);
+    assert_eq!(covered_area(&claims, 2), 0);
+  }
+
+  #[test]
+  fn test_covered_area_huge_claims() {
+    let claims = [
+      Rectangle { id: 1, x: 0, y: 0, w: u32::MAX, h: u32::MAX },
+      Rectangle { id: 2, x: 1, y: 1, w: u32::MAX, h: u32::MAX },
+    ];
+
+    let side = u64::from(u32::MAX) - 1;
+    assert_eq!(covered_area(&claims, 2), side * side);
+  }
+
+  #[test]
+  fn test_union_area() {
+    let claims = [
+      Rectangle { id: 1, x: 1, y: 3, w: 4, h: 4 },
+      Rectangle { id: 2, x: 3, y: 1, w: 4, h: 4 },
+      Rectangle { id: 3, x: 5, y: 5, w: 2, h: 2 },
+    ];
+
+    assert_eq!(union_area(&claims), 32);
+    assert_eq!(union_area::<Rectangle>(&[]), 0);
+  }
+
+  #[test]
+  fn test_difference_area() {
+    let claims = [
+      Rectangle { id: 1, x: 1, y: 3, w: 4, h: 4 },
+      Rectangle { id: 3, x: 5, y: 5, w: 2, h: 2 },
+    ];
+    let others = [
+      Rectangle { id: 2, x: 3, y: 1, w: 4, h: 4 },
+    ];
+
+    assert_eq!(difference_area(&claims, &others), 12 + 4);
+    assert_eq!(difference_area(&others, &claims), 12);
+  }
+
+  #[test]
+  fn test_covered_area_matches_naive() {
+    for seed in 0..5 {
+      let claims = pseudo_random_claims(30, seed);
+      for k in 1..=4 {
+        assert_eq!(covered_area(&claims, k), naive_covered_area(&claims, k));
+      }
+    }
+  }
+}
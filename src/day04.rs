@@ -1,65 +1,131 @@
 use chrono::prelude::*;
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
 use std::collections::HashMap;
+use std::fs;
 use std::fs::File;
 use std::io::BufReader;
 use std::io::prelude::*;
+use std::process;
+use crate::options;
 
+mod export;
 mod log;
 mod nap;
+mod strategy;
+mod timeline;
+mod validate;
 
-use self::log::{Event, LogEntry};
+use self::log::{Event, LogEntry, NumberedEntry};
 use self::nap::Nap;
+use self::strategy::{Finding, SleepStrategy};
+use self::validate::{Anomaly, Diagnostic};
+
+pub fn solve(input_file: File, options: &[String]) {
+  let mut lenient = false;
+  let mut strategies = Vec::new();
+  let mut top = 1;
+  let mut show_timeline = false;
+  let mut show_heatmap = false;
+  let mut exports = Vec::new();
+
+  for option in options.iter() {
+    match options::split(option) {
+      ("lenient", None) => lenient = true,
+      ("strategy", Some("all")) => strategies.extend(strategy::all()),
+      ("strategy", Some(name)) => strategies.push(strategy::by_name(name).unwrap_or_else(|| {
+        let names: Vec<&str> = strategy::all().iter().map(|s| s.name()).collect();
+        panic!("Unknown strategy {}, expected one of: {}", name, names.join(", "))
+      })),
+      ("top", Some(n)) => top = n.parse().expect("--top takes a number"),
+      ("timeline", None) => show_timeline = true,
+      ("heatmap", None) => show_heatmap = true,
+      (table @ "naps", Some(path)) | (table @ "guards", Some(path)) => {
+        let format = export::Format::from_path(path)
+          .unwrap_or_else(|| panic!("--{} needs a .csv or .json file", table));
+        exports.push((table, path, format));
+      },
+      _ => panic!("Unknown option for day 4: {}", option)
+    }
+  }
 
-pub fn solve(input_file: File) {
   let reader = BufReader::new(input_file);
 
-  let mut log_entries: Vec<LogEntry> = reader
-    .lines()
-    .flatten()
-    .map(|line| line.parse())
-    .flatten()
-    .collect();
+  let mut unrecognized = Vec::new();
+  let mut log_entries: Vec<NumberedEntry> = Vec::new();
 
-  log_entries.sort_by_key(|e| e.timestamp);
+  for (index, line) in reader.lines().map(Result::unwrap).enumerate() {
+    match line.parse() {
+      Ok(entry) => log_entries.push(NumberedEntry { line: index + 1, entry }),
+      Err(()) => unrecognized.push(Diagnostic { line: index + 1, anomaly: Anomaly::Unrecognized })
+    }
+  }
 
-  let naps_by_guard_id = collect_naps(&log_entries);
+  log_entries.sort_by_key(|e| e.entry.timestamp);
 
-  let max_minutes_by_guard_id = naps_by_guard_id.iter().map(|(&guard_id, naps)| {
-    (guard_id, max_minute(naps))
-  }).collect::<HashMap<_, _>>();
+  let (naps_by_guard_id, mut diagnostics) = if lenient {
+    validate::collect_naps_lenient(&log_entries)
+  } else {
+    let diagnostics = validate::validate(&log_entries);
+    if diagnostics.is_empty() && unrecognized.is_empty() {
+      (collect_naps(&log_entries), diagnostics)
+    } else {
+      (HashMap::new(), diagnostics)
+    }
+  };
 
-  let (guard_id_1, max_minute_1) = strategy_1(&naps_by_guard_id, &max_minutes_by_guard_id);
-  let (guard_id_2, max_minute_2) = strategy_2(&max_minutes_by_guard_id);
+  diagnostics.extend(unrecognized);
+  diagnostics.sort_by_key(|diagnostic| diagnostic.line);
 
-  println!(
-    "Strategy 1: Guard {} * Minute {} = {}",
-    guard_id_1, max_minute_1, guard_id_1 * max_minute_1
-  );
-  println!(
-    "Strategy 2: Guard {} * Minute {} = {}",
-    guard_id_2, max_minute_2, guard_id_2 * max_minute_2
-  );
-}
+  for diagnostic in diagnostics.iter() {
+    eprintln!("{}", diagnostic);
+  }
 
-fn strategy_1(naps_by_guard_id: &HashMap<u32, Vec<Nap>>, max_minutes_by_guard_id: &HashMap<u32, (u32, u32)>) -> (u32, u32) {
-  let &guard_id = naps_by_guard_id.iter()
-    .max_by_key(|(_, naps)| naps.iter()
-      .map(|nap| nap.len().num_minutes())
-      .sum::<i64>())
-    .unwrap()
-    .0;
+  if !lenient && !diagnostics.is_empty() {
+    eprintln!("The guard log has problems. Run with --lenient to skip over them.");
+    process::exit(1);
+  }
 
-  let max_minute = max_minutes_by_guard_id[&guard_id].0;
+  if naps_by_guard_id.is_empty() {
+    println!("No guard ever falls asleep");
+    return;
+  }
 
-  (guard_id, max_minute)
-}
+  for (table, path, format) in exports {
+    let contents = match table {
+      "naps" => export::export_naps(format, &naps_by_guard_id),
+      _ => export::export_guards(format, &naps_by_guard_id)
+    };
+    fs::write(path, contents).expect("couldn't write export file");
+  }
 
-fn strategy_2(max_minutes_by_guard_id: &HashMap<u32, (u32, u32)>) -> (u32, u32) {
-  let (&guard_id, &(max_minute, _)) = max_minutes_by_guard_id.iter()
-    .max_by_key(|(_, (_, n))| n)
-    .unwrap();
+  if show_timeline {
+    print!("{}", timeline::render_timeline(&log_entries, &naps_by_guard_id));
+  }
+  if show_heatmap {
+    print!("{}", timeline::render_heatmap(&log_entries, &naps_by_guard_id));
+  }
+
+  if strategies.is_empty() {
+    let strategies: [(&str, &dyn SleepStrategy); 2] = [
+      ("Strategy 1", &strategy::MostAsleep),
+      ("Strategy 2", &strategy::MostFrequentMinute)
+    ];
+
+    for (label, strategy) in strategies.iter() {
+      let Finding { guard_id, time, .. } = strategy.best(&naps_by_guard_id).unwrap();
+      let minute = minute_of_day(time);
+      println!("{}: Guard {} * Minute {} = {}", label, guard_id, minute, guard_id * minute);
+    }
+  }
 
-  (guard_id, max_minute)
+  for strategy in strategies.iter() {
+    println!("{} ({}):", strategy.name(), strategy.description());
+    for Finding { guard_id, time, score } in strategy.rank(&naps_by_guard_id).into_iter().take(top) {
+      let minute = minute_of_day(time);
+      println!("  Guard {} * Minute {} = {} (score {})", guard_id, minute, guard_id * minute, score);
+    }
+  }
 }
 
 fn collect_naps<T: AsRef<LogEntry>>(sorted_log_entries: &[T]) -> HashMap<u32, Vec<Nap>> {
@@ -90,51 +156,61 @@ fn collect_naps<T: AsRef<LogEntry>>(sorted_log_entries: &[T]) -> HashMap<u32, Ve
   naps_by_guard_id
 }
 
-fn max_minute<T: AsRef<Nap>>(naps: &[T]) -> (u32, u32) {
-  let mut minutes = HashMap::new();
+/// The time of day the guard was most often asleep, and how many times they
+/// were asleep then. Ties go to the earliest time.
+fn max_minute<T: AsRef<Nap>>(naps: &[T]) -> (NaiveTime, u32) {
+  let mut minutes = BTreeMap::new();
 
   for nap in naps.iter() {
     for timestamp in nap.as_ref().minutes() {
-      *minutes.entry(timestamp.minute()).or_insert(0) += 1;
+      *minutes.entry(timestamp.time()).or_insert(0) += 1;
     }
   }
 
-  let (&minute, &n) = minutes.iter()
-    .max_by_key(|(_, &n)| n)
+  let (&time, &n) = minutes.iter()
+    .max_by_key(|&(&time, &n)| (n, Reverse(time)))
     .unwrap();
 
-  (minute, n)
+  (time, n)
+}
+
+/// Minutes since midnight, which is just the minute for naps in the
+/// midnight hour.
+fn minute_of_day(time: NaiveTime) -> u32 {
+  time.num_seconds_from_midnight() / 60
+}
+
+/// The example log from the puzzle, for tests.
+#[cfg(test)]
+fn example_log_entries() -> Vec<LogEntry> {
+  vec![
+    "[1518-11-01 00:00] Guard #10 begins shift".parse().unwrap(),
+    "[1518-11-01 00:05] falls asleep".parse().unwrap(),
+    "[1518-11-01 00:25] wakes up".parse().unwrap(),
+    "[1518-11-01 00:30] falls asleep".parse().unwrap(),
+    "[1518-11-01 00:55] wakes up".parse().unwrap(),
+    "[1518-11-01 23:58] Guard #99 begins shift".parse().unwrap(),
+    "[1518-11-02 00:40] falls asleep".parse().unwrap(),
+    "[1518-11-02 00:50] wakes up".parse().unwrap(),
+    "[1518-11-03 00:05] Guard #10 begins shift".parse().unwrap(),
+    "[1518-11-03 00:24] falls asleep".parse().unwrap(),
+    "[1518-11-03 00:29] wakes up".parse().unwrap(),
+    "[1518-11-04 00:02] Guard #99 begins shift".parse().unwrap(),
+    "[1518-11-04 00:36] falls asleep".parse().unwrap(),
+    "[1518-11-04 00:46] wakes up".parse().unwrap(),
+    "[1518-11-05 00:03] Guard #99 begins shift".parse().unwrap(),
+    "[1518-11-05 00:45] falls asleep".parse().unwrap(),
+    "[1518-11-05 00:55] wakes up".parse().unwrap(),
+  ]
 }
 
 #[cfg(test)]
 mod tests {
   use super::*;
 
-  fn log_entries() -> Vec<LogEntry> {
-    vec![
-      "[1518-11-01 00:00] Guard #10 begins shift".parse().unwrap(),
-      "[1518-11-01 00:05] falls asleep".parse().unwrap(),
-      "[1518-11-01 00:25] wakes up".parse().unwrap(),
-      "[1518-11-01 00:30] falls asleep".parse().unwrap(),
-      "[1518-11-01 00:55] wakes up".parse().unwrap(),
-      "[1518-11-01 23:58] Guard #99 begins shift".parse().unwrap(),
-      "[1518-11-02 00:40] falls asleep".parse().unwrap(),
-      "[1518-11-02 00:50] wakes up".parse().unwrap(),
-      "[1518-11-03 00:05] Guard #10 begins shift".parse().unwrap(),
-      "[1518-11-03 00:24] falls asleep".parse().unwrap(),
-      "[1518-11-03 00:29] wakes up".parse().unwrap(),
-      "[1518-11-04 00:02] Guard #99 begins shift".parse().unwrap(),
-      "[1518-11-04 00:36] falls asleep".parse().unwrap(),
-      "[1518-11-04 00:46] wakes up".parse().unwrap(),
-      "[1518-11-05 00:03] Guard #99 begins shift".parse().unwrap(),
-      "[1518-11-05 00:45] falls asleep".parse().unwrap(),
-      "[1518-11-05 00:55] wakes up".parse().unwrap(),
-    ]
-  }
-
   #[test]
   fn test_collect_naps() {
-    let entries = log_entries();
+    let entries = example_log_entries();
     let naps_by_guard_id = collect_naps(&entries);
 
     assert_eq!(
@@ -193,35 +269,54 @@ mod tests {
 
     assert_eq!(
       max_minute(&naps),
-      (24, 2)
+      (NaiveTime::from_hms(0, 24, 0), 2)
     );
   }
 
   #[test]
-  fn test_strategy_1() {
-    let entries = log_entries();
-    let naps_by_guard_id = collect_naps(&entries);
-    let max_minutes_by_guard_id = naps_by_guard_id.iter().map(|(&guard_id, naps)| {
-      (guard_id, max_minute(naps))
-    }).collect::<HashMap<_, _>>();
+  fn test_max_minute_keeps_hours_apart() {
+    let naps = vec![
+      Nap::new(
+        Utc.ymd(1518, 11, 1).and_hms(23, 50, 0),
+        Utc.ymd(1518, 11, 2).and_hms(0, 10, 0)
+      ),
+      Nap::new(
+        Utc.ymd(1518, 11, 2).and_hms(1, 55, 0),
+        Utc.ymd(1518, 11, 2).and_hms(2, 0, 0)
+      ),
+      Nap::new(
+        Utc.ymd(1518, 11, 3).and_hms(0, 5, 0),
+        Utc.ymd(1518, 11, 3).and_hms(0, 6, 0)
+      )
+    ];
 
     assert_eq!(
-      strategy_1(&naps_by_guard_id, &max_minutes_by_guard_id),
-      (10, 24)
+      max_minute(&naps),
+      (NaiveTime::from_hms(0, 5, 0), 2)
     );
   }
 
+  #[test]
+  fn test_minute_of_day() {
+    assert_eq!(minute_of_day(NaiveTime::from_hms(0, 45, 0)), 45);
+    assert_eq!(minute_of_day(NaiveTime::from_hms(23, 58, 0)), 1438);
+  }
+
+  #[test]
+  fn test_strategy_1() {
+    let entries = example_log_entries();
+    let naps_by_guard_id = collect_naps(&entries);
+    let Finding { guard_id, time, .. } = strategy::MostAsleep.best(&naps_by_guard_id).unwrap();
+
+    assert_eq!((guard_id, minute_of_day(time)), (10, 24));
+  }
+
   #[test]
   fn test_strategy_2() {
-    let entries = log_entries();
+    let entries = example_log_entries();
     let naps_by_guard_id = collect_naps(&entries);
-    let max_minutes_by_guard_id = naps_by_guard_id.iter().map(|(&guard_id, naps)| {
-      (guard_id, max_minute(naps))
-    }).collect::<HashMap<_, _>>();
+    let Finding { guard_id, time, .. } = strategy::MostFrequentMinute.best(&naps_by_guard_id).unwrap();
 
-    assert_eq!(
-      strategy_2(&max_minutes_by_guard_id),
-      (99, 45)
-    );
+    assert_eq!((guard_id, minute_of_day(time)), (99, 45));
   }
 }
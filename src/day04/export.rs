@@ -0,0 +1,201 @@
+use std::collections::HashMap;
+
+use super::{max_minute, minute_of_day};
+use super::nap::Nap;
+
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Format {
+  Csv,
+  Json
+}
+
+impl Format {
+  /// Picks the format from a file name's extension.
+  pub fn from_path(path: &str) -> Option<Format> {
+    match path.rsplit('.').next() {
+      Some("csv") => Some(Format::Csv),
+      Some("json") => Some(Format::Json),
+      _ => None
+    }
+  }
+}
+
+// Renders rows of already formatted values, quoting strings in JSON.
+fn render(format: Format, columns: &[&str], rows: &[Vec<(String, bool)>]) -> String {
+  match format {
+    Format::Csv => {
+      let mut csv = columns.join(",") + "\n";
+      for row in rows.iter() {
+        let values: Vec<&str> = row.iter().map(|(value, _)| value.as_str()).collect();
+        csv.push_str(&values.join(","));
+        csv.push('\n');
+      }
+      csv
+    },
+    Format::Json => {
+      let objects: Vec<String> = rows.iter()
+        .map(|row| {
+          let fields: Vec<String> = columns.iter()
+            .zip(row.iter())
+            .map(|(column, (value, quoted))| {
+              if *quoted {
+                format!("\"{}\": \"{}\"", column, value)
+              } else {
+                format!("\"{}\": {}", column, value)
+              }
+            })
+            .collect();
+          format!("  {{{}}}", fields.join(", "))
+        })
+        .collect();
+
+      if objects.is_empty() {
+        "[]\n".to_string()
+      } else {
+        format!("[\n{}\n]\n", objects.join(",\n"))
+      }
+    }
+  }
+}
+
+fn number<T: ToString>(value: T) -> (String, bool) {
+  (value.to_string(), false)
+}
+
+fn string<T: ToString>(value: T) -> (String, bool) {
+  (value.to_string(), true)
+}
+
+/// One row per nap, ordered by when it started.
+pub fn export_naps(format: Format, naps_by_guard_id: &HashMap<u32, Vec<Nap>>) -> String {
+  let mut naps: Vec<(u32, &Nap)> = naps_by_guard_id.iter()
+    .flat_map(|(&guard_id, naps)| naps.iter().map(move |nap| (guard_id, nap)))
+    .collect();
+  naps.sort_by_key(|&(guard_id, nap)| (nap.start(), guard_id));
+
+  let rows: Vec<Vec<(String, bool)>> = naps.iter()
+    .map(|&(guard_id, nap)| vec![
+      number(guard_id),
+      string(nap.start().format(TIMESTAMP_FORMAT)),
+      string(nap.end().format(TIMESTAMP_FORMAT)),
+      number(nap.len().num_minutes())
+    ])
+    .collect();
+
+  render(format, &["guard_id", "start", "end", "minutes"], &rows)
+}
+
+/// One row per guard with totals over all of their naps, ordered by guard id.
+pub fn export_guards(format: Format, naps_by_guard_id: &HashMap<u32, Vec<Nap>>) -> String {
+  let mut guard_ids: Vec<u32> = naps_by_guard_id.keys().cloned().collect();
+  guard_ids.sort();
+
+  let rows: Vec<Vec<(String, bool)>> = guard_ids.iter()
+    .map(|guard_id| {
+      let naps = &naps_by_guard_id[guard_id];
+      let (time, times_asleep) = max_minute(naps);
+      vec![
+        number(guard_id),
+        number(naps.len()),
+        number(naps.iter().map(|nap| nap.len().num_minutes()).sum::<i64>()),
+        number(naps.iter().map(|nap| nap.len().num_minutes()).max().unwrap_or(0)),
+        string(time.format("%H:%M")),
+        number(minute_of_day(time)),
+        number(times_asleep)
+      ]
+    })
+    .collect();
+
+  render(
+    format,
+    &["guard_id", "naps", "minutes_asleep", "longest_nap", "sleepiest_time", "sleepiest_minute", "times_asleep_then"],
+    &rows
+  )
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use chrono::prelude::*;
+
+  fn naps_by_guard_id() -> HashMap<u32, Vec<Nap>> {
+    let mut naps_by_guard_id = HashMap::new();
+    naps_by_guard_id.insert(10, vec![
+      Nap::new(
+        Utc.ymd(1518, 11, 1).and_hms(0, 5, 0),
+        Utc.ymd(1518, 11, 1).and_hms(0, 25, 0)
+      ),
+      Nap::new(
+        Utc.ymd(1518, 11, 3).and_hms(0, 24, 0),
+        Utc.ymd(1518, 11, 3).and_hms(0, 29, 0)
+      )
+    ]);
+    naps_by_guard_id.insert(99, vec![
+      Nap::new(
+        Utc.ymd(1518, 11, 2).and_hms(0, 40, 0),
+        Utc.ymd(1518, 11, 2).and_hms(0, 50, 0)
+      )
+    ]);
+    naps_by_guard_id
+  }
+
+  #[test]
+  fn test_format_from_path() {
+    assert_eq!(Format::from_path("naps.csv"), Some(Format::Csv));
+    assert_eq!(Format::from_path("out/guards.json"), Some(Format::Json));
+    assert_eq!(Format::from_path("naps.txt"), None);
+  }
+
+  #[test]
+  fn test_export_naps_csv() {
+    assert_eq!(
+      export_naps(Format::Csv, &naps_by_guard_id()),
+      "\
+guard_id,start,end,minutes
+10,1518-11-01 00:05,1518-11-01 00:25,20
+99,1518-11-02 00:40,1518-11-02 00:50,10
+10,1518-11-03 00:24,1518-11-03 00:29,5
+"
+    );
+  }
+
+  #[test]
+  fn test_export_naps_json() {
+    assert_eq!(
+      export_naps(Format::Json, &naps_by_guard_id()),
+      r#"[
+  {"guard_id": 10, "start": "1518-11-01 00:05", "end": "1518-11-01 00:25", "minutes": 20},
+  {"guard_id": 99, "start": "1518-11-02 00:40", "end": "1518-11-02 00:50", "minutes": 10},
+  {"guard_id": 10, "start": "1518-11-03 00:24", "end": "1518-11-03 00:29", "minutes": 5}
+]
+"#
+    );
+    assert_eq!(export_naps(Format::Json, &HashMap::new()), "[]\n");
+  }
+
+  #[test]
+  fn test_export_guards_csv() {
+    assert_eq!(
+      export_guards(Format::Csv, &naps_by_guard_id()),
+      "\
+guard_id,naps,minutes_asleep,longest_nap,sleepiest_time,sleepiest_minute,times_asleep_then
+10,2,25,20,00:24,24,2
+99,1,10,10,00:40,40,1
+"
+    );
+  }
+
+  #[test]
+  fn test_export_guards_json() {
+    assert_eq!(
+      export_guards(Format::Json, &naps_by_guard_id()),
+      r#"[
+  {"guard_id": 10, "naps": 2, "minutes_asleep": 25, "longest_nap": 20, "sleepiest_time": "00:24", "sleepiest_minute": 24, "times_asleep_then": 2},
+  {"guard_id": 99, "naps": 1, "minutes_asleep": 10, "longest_nap": 10, "sleepiest_time": "00:40", "sleepiest_minute": 40, "times_asleep_then": 1}
+]
+"#
+    );
+  }
+}
@@ -28,17 +28,18 @@ impl FromStr for LogEntry {
     }
 
     if let Some(caps) = LINE_REGEX.captures(s) {
-      let year:   i32 = caps.get(1).unwrap().as_str().parse().unwrap();
-      let month:  u32 = caps.get(2).unwrap().as_str().parse().unwrap();
-      let day:    u32 = caps.get(3).unwrap().as_str().parse().unwrap();
-      let hour:   u32 = caps.get(4).unwrap().as_str().parse().unwrap();
-      let minute: u32 = caps.get(5).unwrap().as_str().parse().unwrap();
+      let number = |index| caps.get(index).unwrap().as_str().parse().map_err(|_| ());
+      let year: i32 = caps.get(1).unwrap().as_str().parse().map_err(|_| ())?;
+      let (month, day, hour, minute): (u32, u32, u32, u32) = (number(2)?, number(3)?, number(4)?, number(5)?);
 
-      let timestamp = Utc.ymd(year, month, day).and_hms(hour, minute, 0);
+      let timestamp = Utc.ymd_opt(year, month, day)
+        .single()
+        .and_then(|date| date.and_hms_opt(hour, minute, 0))
+        .ok_or(())?;
 
       let event_str = caps.get(6).unwrap().as_str();
       if let Some(caps) = BEGIN_SHIFT_REGEX.captures(event_str) {
-        let guard_id: u32 = caps.get(1).unwrap().as_str().parse().unwrap();
+        let guard_id: u32 = caps.get(1).unwrap().as_str().parse().map_err(|_| ())?;
         Ok(LogEntry { timestamp, event: Event::BeginShift(guard_id) })
       } else if event_str == "falls asleep" {
         Ok(LogEntry { timestamp, event: Event::FallAsleep })
@@ -59,6 +60,20 @@ impl AsRef<LogEntry> for LogEntry {
   }
 }
 
+/// A log entry along with the line of the input it was read from, so that
+/// problems can still be traced back to the input once entries are sorted.
+#[derive(Debug, PartialEq)]
+pub struct NumberedEntry {
+  pub line: usize,
+  pub entry: LogEntry
+}
+
+impl AsRef<LogEntry> for NumberedEntry {
+  fn as_ref(&self) -> &LogEntry {
+    &self.entry
+  }
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -87,4 +102,12 @@ mod tests {
       }
     );
   }
+
+  #[test]
+  fn test_log_entry_parse_invalid_numbers() {
+    assert_eq!("[1518-13-01 00:00] falls asleep".parse::<LogEntry>(), Err(()));
+    assert_eq!("[1518-11-01 24:00] falls asleep".parse::<LogEntry>(), Err(()));
+    assert_eq!("[99999999999-11-01 00:00] falls asleep".parse::<LogEntry>(), Err(()));
+    assert_eq!("[1518-11-01 00:00] Guard #99999999999 begins shift".parse::<LogEntry>(), Err(()));
+  }
 }
@@ -12,8 +12,18 @@ impl Nap {
     Nap { start, end }
   }
 
+  pub fn start(&self) -> DateTime<Utc> {
+    self.start
+  }
+
+  pub fn end(&self) -> DateTime<Utc> {
+    self.end
+  }
+
+  /// How long the guard was asleep. The guard wakes up at `end`, so that
+  /// minute isn't counted.
   pub fn len(&self) -> Duration {
-    self.end - self.start - Duration::minutes(1)
+    self.end.signed_duration_since(self.start)
   }
 
   pub fn minutes(&self) -> Minutes {
@@ -62,7 +72,18 @@ mod tests {
       Utc.ymd(2018, 12, 22).and_hms(1, 6, 0)
     );
 
-    assert_eq!(nap.len(), Duration::minutes(60));
+    assert_eq!(nap.len(), Duration::minutes(61));
+    assert_eq!(nap.len(), Duration::minutes(nap.minutes().count() as i64));
+  }
+
+  #[test]
+  fn test_len_across_days() {
+    let nap = Nap::new(
+      Utc.ymd(2018, 12, 31).and_hms(23, 50, 0),
+      Utc.ymd(2019, 1, 1).and_hms(0, 10, 0)
+    );
+
+    assert_eq!(nap.len(), Duration::minutes(20));
   }
 
   #[test]
@@ -83,4 +104,21 @@ mod tests {
       ]
     );
   }
+
+  #[test]
+  fn test_minutes_across_hours() {
+    let nap = Nap::new(
+      Utc.ymd(2018, 12, 22).and_hms(23, 58, 0),
+      Utc.ymd(2018, 12, 23).and_hms(0, 1, 0)
+    );
+
+    assert_eq!(
+      nap.minutes().collect::<Vec<_>>(),
+      vec![
+        Utc.ymd(2018, 12, 22).and_hms(23, 58, 0),
+        Utc.ymd(2018, 12, 22).and_hms(23, 59, 0),
+        Utc.ymd(2018, 12, 23).and_hms(0, 0, 0)
+      ]
+    );
+  }
 }
@@ -0,0 +1,233 @@
+use chrono::prelude::*;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+use super::max_minute;
+use super::nap::Nap;
+
+/// A guard picked out by a strategy, the time of day to sneak in at, and how
+/// well the guard scored on the strategy's metric.
+#[derive(Debug, PartialEq)]
+pub struct Finding {
+  pub guard_id: u32,
+  pub time: NaiveTime,
+  pub score: i64
+}
+
+pub trait SleepStrategy {
+  fn name(&self) -> &'static str;
+
+  fn description(&self) -> &'static str;
+
+  /// Scores every guard who ever fell asleep, best first.
+  fn rank(&self, naps_by_guard_id: &HashMap<u32, Vec<Nap>>) -> Vec<Finding>;
+
+  fn best(&self, naps_by_guard_id: &HashMap<u32, Vec<Nap>>) -> Option<Finding> {
+    self.rank(naps_by_guard_id).into_iter().next()
+  }
+}
+
+// Sorts by score, highest first, breaking ties by guard id so the results
+// don't depend on `HashMap` iteration order.
+fn sorted(mut findings: Vec<Finding>) -> Vec<Finding> {
+  findings.sort_by_key(|finding| (-finding.score, finding.guard_id));
+  findings
+}
+
+/// Strategy 1: the guard with the most minutes asleep, at the minute they're
+/// most often asleep.
+pub struct MostAsleep;
+
+impl SleepStrategy for MostAsleep {
+  fn name(&self) -> &'static str { "most-asleep" }
+
+  fn description(&self) -> &'static str {
+    "guard with the most minutes asleep, at their sleepiest minute"
+  }
+
+  fn rank(&self, naps_by_guard_id: &HashMap<u32, Vec<Nap>>) -> Vec<Finding> {
+    sorted(naps_by_guard_id.iter().map(|(&guard_id, naps)| Finding {
+      guard_id,
+      time: max_minute(naps).0,
+      score: naps.iter().map(|nap| nap.len().num_minutes()).sum()
+    }).collect())
+  }
+}
+
+/// Strategy 2: the guard most frequently asleep on the same minute.
+pub struct MostFrequentMinute;
+
+impl SleepStrategy for MostFrequentMinute {
+  fn name(&self) -> &'static str { "most-frequent-minute" }
+
+  fn description(&self) -> &'static str {
+    "guard most frequently asleep on the same minute"
+  }
+
+  fn rank(&self, naps_by_guard_id: &HashMap<u32, Vec<Nap>>) -> Vec<Finding> {
+    sorted(naps_by_guard_id.iter().map(|(&guard_id, naps)| {
+      let (time, n) = max_minute(naps);
+      Finding { guard_id, time, score: i64::from(n) }
+    }).collect())
+  }
+}
+
+/// The guard who falls asleep on the most different days, at the minute
+/// they're most often asleep.
+pub struct MostConsistent;
+
+impl SleepStrategy for MostConsistent {
+  fn name(&self) -> &'static str { "most-consistent" }
+
+  fn description(&self) -> &'static str {
+    "guard who falls asleep on the most days, at their sleepiest minute"
+  }
+
+  fn rank(&self, naps_by_guard_id: &HashMap<u32, Vec<Nap>>) -> Vec<Finding> {
+    sorted(naps_by_guard_id.iter().map(|(&guard_id, naps)| {
+      let days: HashSet<Date<Utc>> = naps.iter().map(|nap| nap.start().date()).collect();
+      Finding { guard_id, time: max_minute(naps).0, score: days.len() as i64 }
+    }).collect())
+  }
+}
+
+/// The guard with the longest single nap, at the minute it started.
+pub struct LongestNap;
+
+impl SleepStrategy for LongestNap {
+  fn name(&self) -> &'static str { "longest-nap" }
+
+  fn description(&self) -> &'static str {
+    "guard with the longest single nap, at the minute it started"
+  }
+
+  fn rank(&self, naps_by_guard_id: &HashMap<u32, Vec<Nap>>) -> Vec<Finding> {
+    sorted(naps_by_guard_id.iter().map(|(&guard_id, naps)| {
+      let longest = naps.iter().max_by_key(|nap| nap.len()).unwrap();
+      Finding {
+        guard_id,
+        time: longest.start().time(),
+        score: longest.len().num_minutes()
+      }
+    }).collect())
+  }
+}
+
+/// For each guard, the time of day that the most guards (including them)
+/// have ever been asleep at.
+pub struct MostSharedMinute;
+
+impl SleepStrategy for MostSharedMinute {
+  fn name(&self) -> &'static str { "most-shared-minute" }
+
+  fn description(&self) -> &'static str {
+    "time of day the most different guards have been asleep at"
+  }
+
+  fn rank(&self, naps_by_guard_id: &HashMap<u32, Vec<Nap>>) -> Vec<Finding> {
+    let times_by_guard_id: HashMap<u32, HashSet<NaiveTime>> = naps_by_guard_id.iter()
+      .map(|(&guard_id, naps)| {
+        (guard_id, naps.iter().flat_map(Nap::minutes).map(|t| t.time()).collect())
+      })
+      .collect();
+
+    let mut guards_by_time: HashMap<NaiveTime, i64> = HashMap::new();
+    for times in times_by_guard_id.values() {
+      for &time in times.iter() {
+        *guards_by_time.entry(time).or_insert(0) += 1;
+      }
+    }
+
+    sorted(times_by_guard_id.iter()
+      .filter_map(|(&guard_id, times)| {
+        times.iter()
+          .map(|&time| (guards_by_time[&time], time))
+          .max_by_key(|&(n, time)| (n, Reverse(time)))
+          .map(|(score, time)| Finding { guard_id, time, score })
+      })
+      .collect())
+  }
+}
+
+pub fn all() -> Vec<Box<dyn SleepStrategy>> {
+  vec![
+    Box::new(MostAsleep),
+    Box::new(MostFrequentMinute),
+    Box::new(MostConsistent),
+    Box::new(LongestNap),
+    Box::new(MostSharedMinute)
+  ]
+}
+
+pub fn by_name(name: &str) -> Option<Box<dyn SleepStrategy>> {
+  all().into_iter().find(|strategy| strategy.name() == name)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use super::super::collect_naps;
+  use super::super::example_log_entries;
+
+  fn naps_by_guard_id() -> HashMap<u32, Vec<Nap>> {
+    collect_naps(&example_log_entries())
+  }
+
+  #[test]
+  fn test_most_asleep() {
+    assert_eq!(
+      MostAsleep.rank(&naps_by_guard_id()),
+      vec![
+        Finding { guard_id: 10, time: NaiveTime::from_hms(0, 24, 0), score: 50 },
+        Finding { guard_id: 99, time: NaiveTime::from_hms(0, 45, 0), score: 30 },
+      ]
+    );
+  }
+
+  #[test]
+  fn test_most_frequent_minute() {
+    assert_eq!(
+      MostFrequentMinute.best(&naps_by_guard_id()),
+      Some(Finding { guard_id: 99, time: NaiveTime::from_hms(0, 45, 0), score: 3 })
+    );
+  }
+
+  #[test]
+  fn test_most_consistent() {
+    assert_eq!(
+      MostConsistent.rank(&naps_by_guard_id()),
+      vec![
+        Finding { guard_id: 99, time: NaiveTime::from_hms(0, 45, 0), score: 3 },
+        Finding { guard_id: 10, time: NaiveTime::from_hms(0, 24, 0), score: 2 },
+      ]
+    );
+  }
+
+  #[test]
+  fn test_longest_nap() {
+    assert_eq!(
+      LongestNap.best(&naps_by_guard_id()),
+      Some(Finding { guard_id: 10, time: NaiveTime::from_hms(0, 30, 0), score: 25 })
+    );
+  }
+
+  #[test]
+  fn test_most_shared_minute() {
+    assert_eq!(
+      MostSharedMinute.rank(&naps_by_guard_id()),
+      vec![
+        Finding { guard_id: 10, time: NaiveTime::from_hms(0, 36, 0), score: 2 },
+        Finding { guard_id: 99, time: NaiveTime::from_hms(0, 36, 0), score: 2 },
+      ]
+    );
+  }
+
+  #[test]
+  fn test_by_name() {
+    for strategy in all() {
+      assert_eq!(by_name(strategy.name()).unwrap().name(), strategy.name());
+    }
+    assert!(by_name("nope").is_none());
+  }
+}
@@ -0,0 +1,273 @@
+use chrono::Duration;
+use chrono::prelude::*;
+use std::collections::HashMap;
+use std::ops::Range;
+
+use super::log::{Event, LogEntry};
+use super::nap::Nap;
+
+const DENSITY: &[u8] = b".123456789";
+const OVERFLOW: char = '+';
+
+#[derive(Debug, PartialEq)]
+pub struct Shift {
+  pub date: Date<Utc>,
+  pub guard_id: u32,
+  pub start: DateTime<Utc>
+}
+
+impl Shift {
+  // Minutes from midnight on the shift's date, negative before midnight.
+  fn offset(&self, timestamp: DateTime<Utc>) -> i64 {
+    timestamp.signed_duration_since(self.date.and_hms(0, 0, 0)).num_minutes()
+  }
+}
+
+/// The shifts in a sorted log. Each shift is dated by the midnight closest to
+/// when it begins, so a shift that begins before midnight counts as part of
+/// the next day, like in the puzzle.
+pub fn shifts<T: AsRef<LogEntry>>(sorted_log_entries: &[T]) -> Vec<Shift> {
+  sorted_log_entries.iter()
+    .map(AsRef::as_ref)
+    .filter_map(|entry| match entry.event {
+      Event::BeginShift(guard_id) => {
+        let date = if entry.timestamp.hour() < 12 {
+          entry.timestamp.date()
+        } else {
+          entry.timestamp.date() + Duration::days(1)
+        };
+        Some(Shift { date, guard_id, start: entry.timestamp })
+      },
+      _ => None
+    })
+    .collect()
+}
+
+// Pairs each shift with the naps the guard took during it: the ones starting
+// after the shift begins but before the guard's next shift.
+fn naps_by_shift<'a>(shifts: &[Shift], naps_by_guard_id: &'a HashMap<u32, Vec<Nap>>) -> Vec<Vec<&'a Nap>> {
+  let mut naps_by_shift = vec![Vec::new(); shifts.len()];
+
+  for (&guard_id, naps) in naps_by_guard_id.iter() {
+    let guard_shifts: Vec<usize> = (0..shifts.len())
+      .filter(|&i| shifts[i].guard_id == guard_id)
+      .collect();
+
+    for nap in naps.iter() {
+      let after = guard_shifts.iter().take_while(|&&i| shifts[i].start <= nap.start()).count();
+      if after > 0 {
+        naps_by_shift[guard_shifts[after - 1]].push(nap);
+      }
+    }
+  }
+
+  naps_by_shift
+}
+
+// The hours that need columns so every nap fits, relative to midnight on the
+// shift's date. This is just the midnight hour for logs like the puzzle's.
+fn hours(shifts: &[Shift], naps_by_shift: &[Vec<&Nap>]) -> Range<i64> {
+  let offsets: Vec<i64> = shifts.iter()
+    .zip(naps_by_shift.iter())
+    .flat_map(|(shift, naps)| naps.iter().flat_map(move |nap| {
+      vec![shift.offset(nap.start()), shift.offset(nap.end()) - 1]
+    }))
+    .collect();
+
+  let first = offsets.iter().min().map_or(0, |&offset| div_floor(offset, 60).min(0));
+  let last = offsets.iter().max().map_or(0, |&offset| div_floor(offset, 60).max(0));
+
+  first..last + 1
+}
+
+fn div_floor(a: i64, b: i64) -> i64 {
+  (a - a.rem_euclid(b)) / b
+}
+
+// How many of `naps` cover each minute of `hours`.
+fn minute_counts(shift: &Shift, naps: &[&Nap], hours: &Range<i64>) -> Vec<u32> {
+  let mut counts = vec![0; (hours.end - hours.start) as usize * 60];
+
+  for timestamp in naps.iter().flat_map(|nap| nap.minutes()) {
+    let index = shift.offset(timestamp) - hours.start * 60;
+    if let Some(count) = counts.get_mut(index as usize) {
+      *count += 1;
+    }
+  }
+
+  counts
+}
+
+// The column labels, then the minutes of each hour written top to bottom,
+// with the hours themselves above them if there's more than the midnight
+// hour.
+fn header(labels: &str, hours: &Range<i64>) -> String {
+  let margin = labels.len();
+  let mut header = String::new();
+
+  if *hours != (0..1) {
+    let hour_labels: String = hours.clone()
+      .map(|hour| format!("{:<60}", format!("{:02}:00", hour.rem_euclid(24))))
+      .collect();
+    header.push_str(&format!("{:margin$}{}\n", "", hour_labels.trim_end(), margin = margin));
+  }
+
+  let count = hours.clone().count();
+  header.push_str(&format!(
+    "{}Minute\n{:margin$}{}\n{:margin$}{}\n",
+    labels,
+    "", "000000000011111111112222222222333333333344444444445555555555".repeat(count),
+    "", "012345678901234567890123456789012345678901234567890123456789".repeat(count),
+    margin = margin
+  ));
+
+  header
+}
+
+fn id_width<I: Iterator<Item = u32>>(guard_ids: I) -> usize {
+  guard_ids.map(|id| format!("#{}", id).len()).max().unwrap_or(0).max(2)
+}
+
+/// Renders the schedule like the puzzle does: one row per shift, with `#`
+/// for each minute the guard was asleep.
+pub fn render_timeline<T: AsRef<LogEntry>>(sorted_log_entries: &[T], naps_by_guard_id: &HashMap<u32, Vec<Nap>>) -> String {
+  let shifts = shifts(sorted_log_entries);
+  let naps_by_shift = naps_by_shift(&shifts, naps_by_guard_id);
+  let hours = hours(&shifts, &naps_by_shift);
+  let width = id_width(shifts.iter().map(|shift| shift.guard_id));
+
+  let mut timeline = header(&format!("Date   {:width$}  ", "ID", width = width), &hours);
+
+  for (shift, naps) in shifts.iter().zip(naps_by_shift.iter()) {
+    let minutes: String = minute_counts(shift, naps, &hours).iter()
+      .map(|&n| if n > 0 { '#' } else { '.' })
+      .collect();
+
+    timeline.push_str(&format!(
+      "{}  {:width$}  {}\n",
+      shift.date.format("%m-%d"), format!("#{}", shift.guard_id), minutes, width = width
+    ));
+  }
+
+  timeline
+}
+
+/// Renders one row per guard showing how many shifts they were asleep at
+/// each minute.
+pub fn render_heatmap<T: AsRef<LogEntry>>(sorted_log_entries: &[T], naps_by_guard_id: &HashMap<u32, Vec<Nap>>) -> String {
+  let shifts = shifts(sorted_log_entries);
+  let naps_by_shift = naps_by_shift(&shifts, naps_by_guard_id);
+  let hours = hours(&shifts, &naps_by_shift);
+
+  let mut guard_ids: Vec<u32> = naps_by_guard_id.keys().cloned().collect();
+  guard_ids.sort();
+
+  let width = id_width(guard_ids.iter().cloned());
+  let mut heatmap = header(&format!("{:width$}  ", "ID", width = width), &hours);
+
+  for guard_id in guard_ids {
+    let mut counts = vec![0; (hours.end - hours.start) as usize * 60];
+    for (shift, naps) in shifts.iter().zip(naps_by_shift.iter()).filter(|(shift, _)| shift.guard_id == guard_id) {
+      for (count, n) in counts.iter_mut().zip(minute_counts(shift, naps, &hours)) {
+        *count += n.min(1);
+      }
+    }
+
+    let minutes: String = counts.iter()
+      .map(|&n| DENSITY.get(n as usize).map_or(OVERFLOW, |&ch| ch as char))
+      .collect();
+
+    heatmap.push_str(&format!("{:width$}  {}\n", format!("#{}", guard_id), minutes, width = width));
+  }
+
+  heatmap
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use super::super::{collect_naps, example_log_entries};
+
+  #[test]
+  fn test_shifts() {
+    assert_eq!(
+      shifts(&example_log_entries())[..2],
+      [
+        Shift {
+          date: Utc.ymd(1518, 11, 1),
+          guard_id: 10,
+          start: Utc.ymd(1518, 11, 1).and_hms(0, 0, 0)
+        },
+        Shift {
+          date: Utc.ymd(1518, 11, 2),
+          guard_id: 99,
+          start: Utc.ymd(1518, 11, 1).and_hms(23, 58, 0)
+        },
+      ]
+    );
+  }
+
+  #[test]
+  fn test_render_timeline() {
+    let entries = example_log_entries();
+    let naps_by_guard_id = collect_naps(&entries);
+
+    assert_eq!(
+      render_timeline(&entries, &naps_by_guard_id),
+      "\
+Date   ID   Minute
+            000000000011111111112222222222333333333344444444445555555555
+            012345678901234567890123456789012345678901234567890123456789
+11-01  #10  .....####################.....#########################.....
+11-02  #99  ........................................##########..........
+11-03  #10  ........................#####...............................
+11-04  #99  ....................................##########..............
+11-05  #99  .............................................##########.....
+"
+    );
+  }
+
+  #[test]
+  fn test_render_heatmap() {
+    let entries = example_log_entries();
+    let naps_by_guard_id = collect_naps(&entries);
+
+    assert_eq!(
+      render_heatmap(&entries, &naps_by_guard_id),
+      "\
+ID   Minute
+     000000000011111111112222222222333333333344444444445555555555
+     012345678901234567890123456789012345678901234567890123456789
+#10  .....111111111111111111121111.1111111111111111111111111.....
+#99  ....................................1111222223222211111.....
+"
+    );
+  }
+
+  #[test]
+  fn test_render_timeline_across_midnight() {
+    let entries: Vec<LogEntry> = vec![
+      "[1518-11-01 22:30] Guard #10 begins shift".parse().unwrap(),
+      "[1518-11-01 23:50] falls asleep".parse().unwrap(),
+      "[1518-11-02 00:10] wakes up".parse().unwrap(),
+      "[1518-11-02 23:55] Guard #7 begins shift".parse().unwrap(),
+      "[1518-11-03 01:58] falls asleep".parse().unwrap(),
+      "[1518-11-03 02:00] wakes up".parse().unwrap(),
+    ];
+    let naps_by_guard_id = collect_naps(&entries);
+    let timeline = render_timeline(&entries, &naps_by_guard_id);
+    let lines: Vec<&str> = timeline.lines().collect();
+
+    assert_eq!(lines[0].trim_end(), format!("{:12}23:00{:55}00:00{:55}01:00", "", "", ""));
+    assert_eq!(lines[1], "Date   ID   Minute");
+    assert_eq!(lines[3].len(), 12 + 180);
+    assert_eq!(
+      lines[4],
+      format!("11-02  #10  {}{}{}{}", ".".repeat(50), "#".repeat(20), ".".repeat(50), ".".repeat(60))
+    );
+    assert_eq!(
+      lines[5],
+      format!("11-03  #7   {}{}", ".".repeat(178), "##")
+    );
+  }
+}
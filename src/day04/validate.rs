@@ -0,0 +1,245 @@
+use chrono::prelude::*;
+use std::collections::HashMap;
+use std::fmt;
+
+use super::log::{Event, NumberedEntry};
+use super::nap::Nap;
+
+#[derive(Debug, PartialEq)]
+pub enum Anomaly {
+  Unrecognized,
+  BeforeFirstShift,
+  NotAsleep,
+  AlreadyAsleep { nap_line: usize },
+  ShiftDuringNap { guard_id: u32, nap_line: usize },
+  DuplicateTimestamp { other_line: usize },
+  UnfinishedNap
+}
+
+/// Something wrong with the log, and the line of the input it was found on.
+#[derive(Debug, PartialEq)]
+pub struct Diagnostic {
+  pub line: usize,
+  pub anomaly: Anomaly
+}
+
+impl fmt::Display for Diagnostic {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "line {}: ", self.line)?;
+
+    match self.anomaly {
+      Anomaly::Unrecognized =>
+        write!(f, "unrecognized log entry"),
+      Anomaly::BeforeFirstShift =>
+        write!(f, "entry comes before any guard begins a shift"),
+      Anomaly::NotAsleep =>
+        write!(f, "guard wakes up without having fallen asleep"),
+      Anomaly::AlreadyAsleep { nap_line } =>
+        write!(f, "guard falls asleep while already asleep since line {}", nap_line),
+      Anomaly::ShiftDuringNap { guard_id, nap_line } =>
+        write!(f, "shift begins while guard #{} is asleep since line {}", guard_id, nap_line),
+      Anomaly::DuplicateTimestamp { other_line } =>
+        write!(f, "same timestamp as line {}", other_line),
+      Anomaly::UnfinishedNap =>
+        write!(f, "guard falls asleep and never wakes up")
+    }
+  }
+}
+
+/// Reports every anomaly in a sorted log. The log is safe to pass to
+/// `collect_naps` if this comes back empty.
+pub fn validate(sorted_log_entries: &[NumberedEntry]) -> Vec<Diagnostic> {
+  collect_naps_lenient(sorted_log_entries).1
+}
+
+/// Like `collect_naps`, but keeps going when the log doesn't make sense.
+/// Entries that can't be interpreted are skipped, a nap interrupted by the
+/// next shift ends when that shift begins, and naps that never end or that
+/// end the same minute they start are dropped. Every anomaly found along
+/// the way is returned with the naps.
+pub fn collect_naps_lenient(sorted_log_entries: &[NumberedEntry]) -> (HashMap<u32, Vec<Nap>>, Vec<Diagnostic>) {
+  let mut naps_by_guard_id: HashMap<u32, Vec<Nap>> = HashMap::new();
+  let mut diagnostics = Vec::new();
+  let mut current_guard_id = None;
+  let mut nap_start: Option<(usize, DateTime<Utc>)> = None;
+  let mut previous: Option<&NumberedEntry> = None;
+
+  for numbered_entry in sorted_log_entries.iter() {
+    let NumberedEntry { line, entry } = numbered_entry;
+    let mut report = |anomaly| diagnostics.push(Diagnostic { line: *line, anomaly });
+
+    if let Some(previous) = previous {
+      if previous.entry.timestamp == entry.timestamp {
+        report(Anomaly::DuplicateTimestamp { other_line: previous.line });
+      }
+    }
+    previous = Some(numbered_entry);
+
+    let guard_id = match (&entry.event, current_guard_id) {
+      (Event::BeginShift(guard_id), _) => {
+        if let (Some((nap_line, start)), Some(sleeping_guard_id)) = (nap_start.take(), current_guard_id) {
+          report(Anomaly::ShiftDuringNap { guard_id: sleeping_guard_id, nap_line });
+          if start < entry.timestamp {
+            naps_by_guard_id
+              .entry(sleeping_guard_id)
+              .or_default()
+              .push(Nap::new(start, entry.timestamp));
+          }
+        }
+
+        current_guard_id = Some(*guard_id);
+        continue;
+      },
+      (_, None) => {
+        report(Anomaly::BeforeFirstShift);
+        continue;
+      },
+      (_, Some(guard_id)) => guard_id
+    };
+
+    match (&entry.event, nap_start) {
+      (Event::FallAsleep, None) => {
+        nap_start = Some((*line, entry.timestamp));
+      },
+      (Event::FallAsleep, Some((nap_line, _))) => {
+        report(Anomaly::AlreadyAsleep { nap_line });
+      },
+      (Event::WakeUp, Some((_, start))) => {
+        if start < entry.timestamp {
+          naps_by_guard_id
+            .entry(guard_id)
+            .or_default()
+            .push(Nap::new(start, entry.timestamp));
+        }
+
+        nap_start = None;
+      },
+      (Event::WakeUp, None) => {
+        report(Anomaly::NotAsleep);
+      },
+      (Event::BeginShift(_), _) => unreachable!()
+    }
+  }
+
+  if let Some((line, _)) = nap_start {
+    diagnostics.push(Diagnostic { line, anomaly: Anomaly::UnfinishedNap });
+  }
+
+  diagnostics.sort_by_key(|diagnostic| diagnostic.line);
+
+  (naps_by_guard_id, diagnostics)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use super::Anomaly::*;
+
+  fn numbered_entries(lines: &[&str]) -> Vec<NumberedEntry> {
+    let mut entries: Vec<NumberedEntry> = lines.iter()
+      .enumerate()
+      .map(|(index, line)| NumberedEntry { line: index + 1, entry: line.parse().unwrap() })
+      .collect();
+
+    entries.sort_by_key(|e| e.entry.timestamp);
+    entries
+  }
+
+  #[test]
+  fn test_validate_valid_log() {
+    let entries = numbered_entries(&[
+      "[1518-11-01 00:00] Guard #10 begins shift",
+      "[1518-11-01 00:05] falls asleep",
+      "[1518-11-01 00:25] wakes up",
+      "[1518-11-01 23:58] Guard #99 begins shift",
+      "[1518-11-02 00:40] falls asleep",
+      "[1518-11-02 00:50] wakes up",
+    ]);
+
+    assert_eq!(validate(&entries), vec![]);
+  }
+
+  #[test]
+  fn test_validate_reports_every_anomaly() {
+    let entries = numbered_entries(&[
+      "[1518-11-01 00:01] falls asleep",
+      "[1518-11-01 00:02] Guard #10 begins shift",
+      "[1518-11-01 00:03] wakes up",
+      "[1518-11-01 00:05] falls asleep",
+      "[1518-11-01 00:06] falls asleep",
+      "[1518-11-01 00:25] wakes up",
+      "[1518-11-01 00:25] falls asleep",
+      "[1518-11-01 23:58] Guard #99 begins shift",
+      "[1518-11-02 01:40] falls asleep",
+    ]);
+
+    assert_eq!(
+      validate(&entries),
+      vec![
+        Diagnostic { line: 1, anomaly: BeforeFirstShift },
+        Diagnostic { line: 3, anomaly: NotAsleep },
+        Diagnostic { line: 5, anomaly: AlreadyAsleep { nap_line: 4 } },
+        Diagnostic { line: 7, anomaly: DuplicateTimestamp { other_line: 6 } },
+        Diagnostic { line: 8, anomaly: ShiftDuringNap { guard_id: 10, nap_line: 7 } },
+        Diagnostic { line: 9, anomaly: UnfinishedNap },
+      ]
+    );
+  }
+
+  #[test]
+  fn test_collect_naps_lenient() {
+    let entries = numbered_entries(&[
+      "[1518-11-01 00:00] Guard #10 begins shift",
+      "[1518-11-01 00:05] falls asleep",
+      "[1518-11-01 00:10] falls asleep",
+      "[1518-11-01 00:25] wakes up",
+      "[1518-11-01 00:30] wakes up",
+      "[1518-11-01 00:40] falls asleep",
+      "[1518-11-01 00:50] Guard #99 begins shift",
+    ]);
+
+    let (naps_by_guard_id, diagnostics) = collect_naps_lenient(&entries);
+
+    assert_eq!(
+      naps_by_guard_id[&10],
+      &[
+        Nap::new(
+          Utc.ymd(1518, 11, 1).and_hms(0, 5, 0),
+          Utc.ymd(1518, 11, 1).and_hms(0, 25, 0)
+        ),
+        Nap::new(
+          Utc.ymd(1518, 11, 1).and_hms(0, 40, 0),
+          Utc.ymd(1518, 11, 1).and_hms(0, 50, 0)
+        )
+      ]
+    );
+    assert!(!naps_by_guard_id.contains_key(&99));
+    assert_eq!(diagnostics.len(), 3);
+  }
+
+  #[test]
+  fn test_collect_naps_lenient_drops_empty_naps() {
+    let entries = numbered_entries(&[
+      "[1518-11-01 00:00] Guard #10 begins shift",
+      "[1518-11-01 00:05] falls asleep",
+      "[1518-11-01 00:05] wakes up",
+    ]);
+
+    let (naps_by_guard_id, diagnostics) = collect_naps_lenient(&entries);
+
+    assert!(naps_by_guard_id.is_empty());
+    assert_eq!(diagnostics, vec![Diagnostic { line: 3, anomaly: DuplicateTimestamp { other_line: 2 } }]);
+  }
+
+  #[test]
+  fn test_diagnostic_display() {
+    assert_eq!(
+      Diagnostic { line: 8, anomaly: ShiftDuringNap { guard_id: 10, nap_line: 7 } }.to_string(),
+      "line 8: shift begins while guard #10 is asleep since line 7"
+    );
+    assert_eq!(
+      Diagnostic { line: 3, anomaly: NotAsleep }.to_string(),
+      "line 3: guard wakes up without having fallen asleep"
+    );
+  }
+}
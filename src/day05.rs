@@ -1,54 +1,215 @@
+use std::collections::BTreeSet;
+use std::fs;
 use std::fs::File;
+use std::io;
 use std::io::BufReader;
 use std::io::prelude::*;
+use std::str;
 use std::str::FromStr;
+use std::thread;
+use crate::options;
 
-#[derive(Debug, PartialEq)]
+mod removal;
+mod rules;
+
+use self::removal::Removal;
+use self::rules::{Reaction, Rules};
+
+#[derive(Debug, PartialEq, Clone)]
 struct Polymer(Vec<Atom>);
 
-#[derive(Debug, PartialEq, Copy, Clone)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
 struct Atom {
   symbol: char,
   polarity: Polarity
 }
 
-#[derive(Debug, PartialEq, Copy, Clone)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
 enum Polarity {
   Up,
   Down
 }
 
 impl Atom {
+  /// Lowercase letters are `Down` and everything else, including symbols
+  /// without a case, is `Up`.
   pub fn from_char(ch: char) -> Self {
-    Atom {
-      symbol: ch.to_ascii_uppercase(),
-      polarity: if ch.is_ascii_uppercase() { Polarity::Up } else { Polarity::Down }
+    match single(ch.to_uppercase()) {
+      Some(symbol) if symbol != ch && single(symbol.to_lowercase()) == Some(ch) =>
+        Atom { symbol, polarity: Polarity::Down },
+      _ => Atom { symbol: ch, polarity: Polarity::Up }
+    }
+  }
+
+  pub fn to_char(self) -> char {
+    match self.polarity {
+      Polarity::Up => self.symbol,
+      Polarity::Down => single(self.symbol.to_lowercase()).unwrap_or(self.symbol)
     }
   }
 
+  /// The same symbol in the other case, if it has one.
+  pub fn opposite(self) -> Option<Atom> {
+    let polarity = match self.polarity {
+      Polarity::Up => Polarity::Down,
+      Polarity::Down => Polarity::Up
+    };
+    let opposite = Atom { symbol: self.symbol, polarity };
+
+    Some(opposite).filter(|opposite| Atom::from_char(opposite.to_char()) == *opposite)
+  }
+
   pub fn reacts_with(self, other: Atom) -> bool {
     self.symbol == other.symbol && self.polarity != other.polarity
   }
 }
 
+fn single<I: Iterator<Item = char>>(mut chars: I) -> Option<char> {
+  match (chars.next(), chars.next()) {
+    (Some(ch), None) => Some(ch),
+    _ => None
+  }
+}
+
+/// Reacts a polymer one atom at a time, keeping only the atoms that haven't
+/// reacted (yet), so the polymer itself never has to be in memory.
+#[derive(Debug)]
+struct Reactor<'a> {
+  rules: &'a Rules,
+  unreacted: Vec<Atom>
+}
+
+impl<'a> Reactor<'a> {
+  pub fn new(rules: &'a Rules) -> Self {
+    Reactor { rules, unreacted: Vec::new() }
+  }
+
+  pub fn push(&mut self, atom: Atom) {
+    let mut atom = atom;
+
+    while let Some(&last_atom) = self.unreacted.last() {
+      match self.rules.reaction(last_atom, atom) {
+        Some(Reaction::Annihilate) => {
+          self.unreacted.pop();
+          return;
+        },
+        // The new unit can go on to react with whatever came before.
+        Some(Reaction::Transform(product)) => {
+          self.unreacted.pop();
+          atom = product;
+        },
+        None => break
+      }
+    }
+
+    self.unreacted.push(atom);
+  }
+
+  /// Reacts every unit read from `reader`, ignoring whitespace. The input has
+  /// to be UTF-8, but characters can be split across reads.
+  pub fn feed<R: BufRead>(&mut self, mut reader: R) -> io::Result<()> {
+    let mut pending = Vec::new();
+
+    loop {
+      let consumed = {
+        let bytes = reader.fill_buf()?;
+        if bytes.is_empty() {
+          break;
+        }
+
+        pending.extend_from_slice(bytes);
+        bytes.len()
+      };
+      reader.consume(consumed);
+
+      // Anything after the last complete character waits for the next read.
+      let valid = match str::from_utf8(&pending) {
+        Ok(text) => text.len(),
+        Err(error) if error.error_len().is_none() => error.valid_up_to(),
+        Err(error) => return Err(io::Error::new(io::ErrorKind::InvalidData, error))
+      };
+
+      for ch in str::from_utf8(&pending[..valid]).unwrap().chars().filter(|ch| !ch.is_whitespace()) {
+        self.push(Atom::from_char(ch));
+      }
+      pending.drain(..valid);
+    }
+
+    if pending.is_empty() {
+      Ok(())
+    } else {
+      Err(io::Error::new(io::ErrorKind::InvalidData, "input ends in the middle of a character"))
+    }
+  }
+
+  pub fn into_polymer(self) -> Polymer {
+    Polymer(self.unreacted)
+  }
+}
+
 impl Polymer {
-  pub fn react(&self) -> Self {
-    let mut reacted: Vec<Atom> = Vec::new();
+  pub fn react(&self, rules: &Rules) -> Self {
+    let mut reactor = Reactor::new(rules);
 
     for &atom in self.0.iter() {
-      match reacted.last() {
-        Some(last_atom) => {
-          if last_atom.reacts_with(atom) {
-            reacted.pop();
-          } else {
-            reacted.push(atom);
+      reactor.push(atom);
+    }
+
+    reactor.into_polymer()
+  }
+
+  /// Reacts the polymer in `num_threads` chunks at once. Whatever's left of
+  /// each chunk can only react with what's left of its neighbors, so the
+  /// chunks are then merged by cancelling atoms across their boundaries.
+  /// That only works for confluent rules, so others react on one thread.
+  pub fn react_parallel(&self, rules: &Rules, num_threads: usize) -> Self {
+    if num_threads <= 1 || !rules.is_confluent() {
+      return self.react(rules);
+    }
+
+    let chunk_size = self.0.len().div_ceil(num_threads).max(1);
+
+    let residues: Vec<Polymer> = thread::scope(|scope| {
+      let handles: Vec<_> = self.0.chunks(chunk_size)
+        .map(|chunk| scope.spawn(move || {
+          let mut reactor = Reactor::new(rules);
+          for &atom in chunk.iter() {
+            reactor.push(atom);
           }
-        },
-        None => reacted.push(atom)
+          reactor.into_polymer()
+        }))
+        .collect();
+
+      handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+    });
+
+    residues.into_iter().fold(Polymer(Vec::new()), |reacted, residue| reacted.merge(residue, rules))
+  }
+
+  // Joins two already reacted polymers, reacting the atoms that meet at the
+  // boundary until they stop reacting.
+  fn merge(self, other: Polymer, rules: &Rules) -> Polymer {
+    let Polymer(mut atoms) = self;
+    let mut rest = other.0.into_iter().peekable();
+
+    while let (Some(&last), Some(&next)) = (atoms.last(), rest.peek()) {
+      if rules.reaction(last, next) != Some(Reaction::Annihilate) {
+        break;
       }
+      atoms.pop();
+      rest.next();
     }
 
-    Polymer(reacted)
+    atoms.extend(rest);
+    Polymer(atoms)
+  }
+
+  /// Reads and reacts a polymer without ever holding the unreacted polymer
+  /// in memory.
+  pub fn react_from<R: BufRead>(reader: R, rules: &Rules) -> io::Result<Self> {
+    let mut reactor = Reactor::new(rules);
+    reactor.feed(reader)?;
+    Ok(reactor.into_polymer())
   }
 
   pub fn remove_symbol(&self, symbol: char) -> Self {
@@ -60,6 +221,12 @@ impl Polymer {
     )
   }
 
+  /// The types of unit in the polymer, whatever their polarity.
+  pub fn symbols(&self) -> Vec<char> {
+    let symbols: BTreeSet<char> = self.0.iter().map(|atom| atom.symbol).collect();
+    symbols.into_iter().collect()
+  }
+
   pub fn len(&self) -> usize {
     self.0.len()
   }
@@ -71,30 +238,54 @@ impl FromStr for Polymer {
   fn from_str(s: &str) -> Result<Self, ()> {
     Ok(Polymer(
       s.chars()
-        .filter(char::is_ascii_alphabetic)
+        .filter(|ch| !ch.is_whitespace())
         .map(Atom::from_char)
         .collect()
     ))
   }
 }
 
-pub fn solve(input_file: File) {
+pub fn solve(input_file: File, options: &[String]) {
+  let mut rules = Rules::default();
+  let mut show_removals = None;
+
+  for option in options.iter() {
+    match options::split(option) {
+      ("removals", None) => show_removals = Some(1),
+      ("removals", Some(n)) => show_removals = Some(n.parse().expect("--removals takes a number")),
+      ("rules", Some(path)) => {
+        let text = fs::read_to_string(path).expect("Couldn't read the rules file");
+        rules = text.parse().unwrap_or_else(|error| panic!("Invalid rules in {}: {}", path, error));
+      },
+      _ => panic!("Unknown option for day 5: {}", option)
+    }
+  }
+
   let mut reader = BufReader::new(input_file);
 
-  let mut input = String::new();
-  reader.read_to_string(&mut input).unwrap();
+  // With confluent rules, removing a unit and reacting gives the same result
+  // whether or not the polymer already reacted, so only the reacted polymer
+  // needs to be kept. Otherwise the whole polymer is needed.
+  let polymer = if rules.is_confluent() {
+    Polymer::react_from(reader, &rules).unwrap()
+  } else {
+    let mut input = String::new();
+    reader.read_to_string(&mut input).unwrap();
+    input.parse().unwrap()
+  };
+  let reacted = polymer.react(&rules);
 
-  let polymer: Polymer = input.parse().unwrap();
+  println!("Length of reacted polymer: {}", reacted.len());
 
-  println!("Length of reacted polymer: {}", polymer.react().len());
-  println!("Shortest length: {}", shortest_length_once_removed(&polymer));
-}
+  let report = removal::removal_report(&polymer, &rules, 1);
+  if let Some(Removal { length, .. }) = removal::shortest(&report) {
+    println!("Shortest length: {}", length);
+  }
 
-fn shortest_length_once_removed(polymer: &Polymer) -> usize {
-  (b'A'..b'Z')
-    .map(|symbol| polymer.remove_symbol(symbol as char).react().len())
-    .min()
-    .unwrap()
+  if let Some(group_size) = show_removals {
+    println!();
+    print!("{}", removal::render_report(&removal::removal_report(&polymer, &rules, group_size)));
+  }
 }
 
 #[cfg(test)]
@@ -144,11 +335,79 @@ mod tests {
     let polymer: Polymer = "dabAcCaCBAcCcaDA".parse().unwrap();
 
     assert_eq!(
-      polymer.react(),
+      polymer.react(&Rules::default()),
+      "dabCBAcaDA".parse().unwrap()
+    );
+  }
+
+  #[test]
+  fn test_polymer_react_from() {
+    let reader = BufReader::with_capacity(3, "dabAcCaCBAcCcaDA\n".as_bytes());
+
+    assert_eq!(
+      Polymer::react_from(reader, &Rules::default()).unwrap(),
       "dabCBAcaDA".parse().unwrap()
     );
   }
 
+  #[test]
+  fn test_reactor_matches_react() {
+    let rules = Rules::default();
+
+    for input in &["", "aA", "abBA", "abAB", "aabAAB", "dabAcCaCBAcCcaDA"] {
+      let polymer: Polymer = input.parse().unwrap();
+      let mut reactor = Reactor::new(&rules);
+      reactor.feed(BufReader::with_capacity(1, input.as_bytes())).unwrap();
+
+      assert_eq!(reactor.into_polymer(), polymer.react(&rules));
+    }
+  }
+
+  #[test]
+  fn test_polymer_react_parallel() {
+    let polymer: Polymer = "dabAcCaCBAcCcaDA".parse().unwrap();
+
+    for num_threads in 1..=20 {
+      assert_eq!(polymer.react_parallel(&Rules::default(), num_threads), polymer.react(&Rules::default()));
+    }
+
+    let empty: Polymer = "".parse().unwrap();
+    assert_eq!(empty.react_parallel(&Rules::default(), 4), empty);
+  }
+
+  #[test]
+  fn test_polymer_react_parallel_cancels_across_chunks() {
+    let polymer: Polymer = "abcdefgGFEDCBA".parse().unwrap();
+
+    assert_eq!(polymer.react_parallel(&Rules::default(), 7).len(), 0);
+
+    let input: String = (0..2000)
+      .map(|i| ["a", "B", "b", "c", "C", "A", "d"][i * 7 % 11 % 7])
+      .collect();
+    let polymer: Polymer = input.parse().unwrap();
+
+    for &num_threads in &[2, 3, 8, 64] {
+      assert_eq!(polymer.react_parallel(&Rules::default(), num_threads), polymer.react(&Rules::default()));
+    }
+  }
+
+  #[test]
+  fn test_shortest_length_once_removed_after_reacting() {
+    let rules = Rules::default();
+    let polymer: Polymer = "dabAcCaCBAcCcaDA".parse().unwrap();
+
+    let report = removal::removal_report(&polymer.react(&rules), &rules, 1);
+
+    assert_eq!(4, removal::shortest(&report).unwrap().length);
+  }
+
+  #[test]
+  fn test_polymer_symbols() {
+    let polymer: Polymer = "dabAcCaCBAcCcaDAω".parse().unwrap();
+
+    assert_eq!(polymer.symbols(), vec!['A', 'B', 'C', 'D', 'Ω']);
+  }
+
   #[test]
   fn test_polymer_remove_symbol() {
     let polymer: Polymer = "dabAcCaCBAcCcaDA".parse().unwrap();
@@ -174,7 +433,61 @@ mod tests {
   #[test]
   fn test_shortest_length_once_removed() {
     let polymer: Polymer = "dabAcCaCBAcCcaDA".parse().unwrap();
+    let report = removal::removal_report(&polymer, &Rules::default(), 1);
+
+    assert_eq!(4, removal::shortest(&report).unwrap().length);
+  }
+
+  #[test]
+  fn test_unit_from_char_beyond_ascii() {
+    assert_eq!(Atom::from_char('ω'), Atom { symbol: 'Ω', polarity: Down });
+    assert_eq!(Atom::from_char('+'), Atom { symbol: '+', polarity: Up });
+    assert_eq!(Atom::from_char('ß'), Atom { symbol: 'ß', polarity: Up });
+
+    for ch in "aZωΩ+ßǅ".chars() {
+      assert_eq!(Atom::from_char(ch).to_char(), ch);
+    }
+    assert_eq!(Atom::from_char('+').opposite(), None);
+  }
 
-    assert_eq!(4, shortest_length_once_removed(&polymer));
+  #[test]
+  fn test_polymer_react_beyond_ascii() {
+    let rules = Rules::default();
+    let polymer: Polymer = "ωΩаБбА+".parse().unwrap();
+
+    assert_eq!(polymer.react(&rules), "+".parse().unwrap());
+    assert_eq!(
+      Polymer::react_from(BufReader::with_capacity(1, "ωΩаБбА+\n".as_bytes()), &rules).unwrap(),
+      "+".parse().unwrap()
+    );
+    assert!(Polymer::react_from("ab\u{e9}".as_bytes()[..3].as_ref(), &rules).is_err());
+  }
+
+  #[test]
+  fn test_polymer_react_with_transforms() {
+    let rules: Rules = "\
+opposite-case
+ab -> c
+cd -> A
+".parse().unwrap();
+    let polymer: Polymer = "aabd".parse().unwrap();
+
+    // ab becomes c, cd becomes A, and aA annihilates.
+    assert_eq!(polymer.react(&rules), Polymer(vec![]));
+
+    let mut reactor = Reactor::new(&rules);
+    reactor.feed(BufReader::with_capacity(1, "xaabdy".as_bytes())).unwrap();
+    assert_eq!(reactor.into_polymer(), "xy".parse().unwrap());
+  }
+
+  #[test]
+  fn test_polymer_react_parallel_with_custom_rules() {
+    let input: String = (0..500).map(|i| ["x", "y", "z", "a"][i * 7 % 11 % 4]).collect();
+    let polymer: Polymer = input.parse().unwrap();
+
+    for rules in &["xy\nyx\n", "xy -> z\nza\n", "xy\n"] {
+      let rules: Rules = rules.parse().unwrap();
+      assert_eq!(polymer.react_parallel(&rules, 8), polymer.react(&rules));
+    }
   }
 }
@@ -0,0 +1,150 @@
+use std::thread;
+
+use super::{Atom, Polarity, Polymer};
+use super::rules::Rules;
+
+/// The reacted length of a polymer after removing every unit of some types.
+#[derive(Debug, PartialEq)]
+pub struct Removal {
+  pub symbols: Vec<char>,
+  pub length: usize
+}
+
+impl Removal {
+  /// The removed units, both polarities of each, like `a/A b/B`.
+  pub fn label(&self) -> String {
+    let labels: Vec<String> = self.symbols.iter()
+      .map(|&symbol| {
+        let down = Atom { symbol, polarity: Polarity::Down }.to_char();
+        if down == symbol { symbol.to_string() } else { format!("{}/{}", down, symbol) }
+      })
+      .collect();
+
+    labels.join(" ")
+  }
+}
+
+// Every way of picking `k` of `items`, keeping them in order.
+fn combinations(items: &[char], k: usize) -> Vec<Vec<char>> {
+  if k == 0 {
+    return vec![vec![]];
+  }
+
+  (0..items.len())
+    .flat_map(|i| {
+      combinations(&items[i + 1..], k - 1).into_iter().map(move |mut rest| {
+        rest.insert(0, items[i]);
+        rest
+      })
+    })
+    .collect()
+}
+
+/// Reacts the polymer once for every combination of `group_size` unit types
+/// found in it, with those units removed.
+pub fn removal_report(polymer: &Polymer, rules: &Rules, group_size: usize) -> Vec<Removal> {
+  let num_threads = thread::available_parallelism().map_or(1, |n| n.get());
+
+  combinations(&polymer.symbols(), group_size).into_iter()
+    .map(|symbols| {
+      let removed = symbols.iter().fold(polymer.clone(), |removed, &symbol| removed.remove_symbol(symbol));
+      let length = removed.react_parallel(rules, num_threads).len();
+      Removal { symbols, length }
+    })
+    .collect()
+}
+
+/// The removal giving the shortest polymer, the first one on ties.
+pub fn shortest(report: &[Removal]) -> Option<&Removal> {
+  report.iter().min_by_key(|removal| removal.length)
+}
+
+pub fn render_report(report: &[Removal]) -> String {
+  let width = report.iter().map(|removal| removal.label().chars().count()).max().unwrap_or(0).max(7);
+  let shortest = shortest(report);
+  let mut rendered = format!("{:width$}  Length\n", "Removed", width = width);
+
+  for removal in report.iter() {
+    let marker = if Some(removal) == shortest { "  <- shortest" } else { "" };
+    rendered.push_str(&format!("{:width$}  {}{}\n", removal.label(), removal.length, marker, width = width));
+  }
+
+  rendered
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_combinations() {
+    assert_eq!(combinations(&['A', 'B', 'C'], 1), vec![vec!['A'], vec!['B'], vec!['C']]);
+    assert_eq!(
+      combinations(&['A', 'B', 'C'], 2),
+      vec![vec!['A', 'B'], vec!['A', 'C'], vec!['B', 'C']]
+    );
+    assert!(combinations(&['A'], 2).is_empty());
+  }
+
+  #[test]
+  fn test_removal_report() {
+    let polymer: Polymer = "dabAcCaCBAcCcaDA".parse().unwrap();
+    let report = removal_report(&polymer, &Rules::default(), 1);
+
+    assert_eq!(
+      report,
+      vec![
+        Removal { symbols: vec!['A'], length: 6 },
+        Removal { symbols: vec!['B'], length: 8 },
+        Removal { symbols: vec!['C'], length: 4 },
+        Removal { symbols: vec!['D'], length: 6 },
+      ]
+    );
+    assert_eq!(shortest(&report), Some(&report[2]));
+  }
+
+  #[test]
+  fn test_removal_report_includes_z() {
+    let polymer: Polymer = "aZA".parse().unwrap();
+    let report = removal_report(&polymer, &Rules::default(), 1);
+
+    assert_eq!(shortest(&report), Some(&Removal { symbols: vec!['Z'], length: 0 }));
+  }
+
+  #[test]
+  fn test_removal_report_pairs() {
+    let polymer: Polymer = "dabAcCaCBAcCcaDA".parse().unwrap();
+    let report = removal_report(&polymer, &Rules::default(), 2);
+
+    let lengths: Vec<(String, usize)> = report.iter().map(|removal| (removal.label(), removal.length)).collect();
+
+    assert_eq!(
+      lengths,
+      vec![
+        ("a/A b/B".to_string(), 0),
+        ("a/A c/C".to_string(), 0),
+        ("a/A d/D".to_string(), 4),
+        ("b/B c/C".to_string(), 4),
+        ("b/B d/D".to_string(), 4),
+        ("c/C d/D".to_string(), 0),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_render_report() {
+    let polymer: Polymer = "dabAcCaCBAcCcaDA+".parse().unwrap();
+
+    assert_eq!(
+      render_report(&removal_report(&polymer, &Rules::default(), 1)),
+      "\
+Removed  Length
++        10
+a/A      7
+b/B      9
+c/C      5  <- shortest
+d/D      7
+"
+    );
+  }
+}
@@ -0,0 +1,167 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use super::Atom;
+
+/// What happens when two units end up next to each other.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Reaction {
+  Annihilate,
+  Transform(Atom)
+}
+
+/// Which pairs of adjacent units react, and what they react into.
+///
+/// A rule file has one rule per line:
+///
+/// ```text
+/// # Comments start with a hash
+/// opposite-case    same letter, opposite case annihilates (the default)
+/// xy               `x` followed by `y` annihilates
+/// xy -> z          `x` followed by `y` becomes `z`
+/// ```
+///
+/// Rules are directional, so `xy` doesn't imply `yx`. Explicit rules take
+/// precedence over `opposite-case`.
+#[derive(Debug, PartialEq)]
+pub struct Rules {
+  opposite_case: bool,
+  reactions: HashMap<(Atom, Atom), Reaction>
+}
+
+impl Default for Rules {
+  /// The puzzle's rules: same letter, opposite case annihilates.
+  fn default() -> Self {
+    Rules { opposite_case: true, reactions: HashMap::new() }
+  }
+}
+
+impl Rules {
+  pub fn reaction(&self, left: Atom, right: Atom) -> Option<Reaction> {
+    match self.reactions.get(&(left, right)) {
+      Some(&reaction) => Some(reaction),
+      None if self.opposite_case && left.reacts_with(right) => Some(Reaction::Annihilate),
+      None => None
+    }
+  }
+
+  // The units that `atom` annihilates with when followed by them.
+  fn partners(&self, atom: Atom) -> Vec<Atom> {
+    let mut partners: Vec<Atom> = self.reactions.keys()
+      .filter(|&&(left, _)| left == atom)
+      .map(|&(_, right)| right)
+      .collect();
+
+    if let Some(opposite) = atom.opposite().filter(|_| self.opposite_case) {
+      if !self.reactions.contains_key(&(atom, opposite)) {
+        partners.push(opposite);
+      }
+    }
+
+    partners
+  }
+
+  /// Whether the result of reacting doesn't depend on which pairs react
+  /// first. That's the case when nothing transforms and every unit
+  /// annihilates with exactly one other unit, from either side, so a polymer
+  /// can be reacted in chunks and the chunks merged afterwards.
+  pub fn is_confluent(&self) -> bool {
+    self.reactions.iter().all(|(&(left, right), &reaction)| {
+      reaction == Reaction::Annihilate
+        && self.reaction(right, left) == Some(Reaction::Annihilate)
+        && self.partners(left).len() == 1
+        && self.partners(right).len() == 1
+    })
+  }
+}
+
+impl FromStr for Rules {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, String> {
+    let mut rules = Rules { opposite_case: false, reactions: HashMap::new() };
+
+    for (index, line) in s.lines().enumerate() {
+      let line = line.split('#').next().unwrap().trim();
+      if line.is_empty() {
+        continue;
+      }
+      if line == "opposite-case" {
+        rules.opposite_case = true;
+        continue;
+      }
+
+      let mut sides = line.splitn(2, "->");
+      let pair: Vec<char> = sides.next().unwrap().trim().chars().collect();
+      if pair.len() != 2 {
+        return Err(format!("line {}: expected a pair of units, got {:?}", index + 1, line));
+      }
+
+      let reaction = match sides.next().map(str::trim) {
+        None => Reaction::Annihilate,
+        Some(product) => {
+          let product: Vec<char> = product.chars().collect();
+          if product.len() != 1 {
+            return Err(format!("line {}: expected a single unit after ->", index + 1));
+          }
+          Reaction::Transform(Atom::from_char(product[0]))
+        }
+      };
+
+      rules.reactions.insert((Atom::from_char(pair[0]), Atom::from_char(pair[1])), reaction);
+    }
+
+    Ok(rules)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_default_rules() {
+    let rules = Rules::default();
+
+    assert_eq!(rules.reaction(Atom::from_char('a'), Atom::from_char('A')), Some(Reaction::Annihilate));
+    assert_eq!(rules.reaction(Atom::from_char('A'), Atom::from_char('a')), Some(Reaction::Annihilate));
+    assert_eq!(rules.reaction(Atom::from_char('a'), Atom::from_char('a')), None);
+    assert_eq!(rules.reaction(Atom::from_char('a'), Atom::from_char('B')), None);
+    assert!(rules.is_confluent());
+  }
+
+  #[test]
+  fn test_rules_from_str() {
+    let rules: Rules = "\
+# Greek
+αΩ
+Ωα
+xy -> z   # x followed by y
+".parse().unwrap();
+
+    assert_eq!(rules.reaction(Atom::from_char('α'), Atom::from_char('Ω')), Some(Reaction::Annihilate));
+    assert_eq!(rules.reaction(Atom::from_char('x'), Atom::from_char('y')), Some(Reaction::Transform(Atom::from_char('z'))));
+    assert_eq!(rules.reaction(Atom::from_char('y'), Atom::from_char('x')), None);
+    assert_eq!(rules.reaction(Atom::from_char('a'), Atom::from_char('A')), None);
+
+    let rules: Rules = "opposite-case\n".parse().unwrap();
+    assert_eq!(rules, Rules::default());
+  }
+
+  #[test]
+  fn test_rules_from_str_errors() {
+    assert_eq!(
+      "aA\nabc\n".parse::<Rules>(),
+      Err("line 2: expected a pair of units, got \"abc\"".to_string())
+    );
+    assert!("ab -> \n".parse::<Rules>().is_err());
+  }
+
+  #[test]
+  fn test_is_confluent() {
+    assert!("αΩ\nΩα\n".parse::<Rules>().unwrap().is_confluent());
+    assert!(!"αΩ\n".parse::<Rules>().unwrap().is_confluent());
+    assert!(!"ab -> c\nba -> c\n".parse::<Rules>().unwrap().is_confluent());
+    assert!(!"opposite-case\naB\nBa\n".parse::<Rules>().unwrap().is_confluent());
+  }
+}
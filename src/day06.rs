@@ -1,163 +1,362 @@
-use std::collections::HashMap;
+use std::convert::TryInto;
+use std::fmt;
 use std::fs::File;
 use std::io::BufReader;
 use std::io::prelude::*;
+use std::iter;
 use std::num::ParseIntError;
 use std::str::FromStr;
+use crate::options;
 
+mod metric;
+mod safe;
+mod territories;
+mod voronoi;
+
+use self::metric::Metric;
+use self::territories::Territories;
+use self::voronoi::Voronoi;
+
+/// A point with `N` integer coordinates, on a plane unless said otherwise.
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
-struct Point(i32, i32);
+struct Point<const N: usize = 2>([i32; N]);
 
-impl Point {
-  pub fn closest(self, points: &[Point]) -> Option<Point> {
-    let mut min_distance = None;
-    let mut closest_point = None;
+impl<const N: usize> Point<N> {
+  pub fn closest(self, points: &[Point<N>], metric: &dyn Metric) -> Option<Point<N>> {
+    let mut closest: Option<(i64, Point<N>)> = None;
     let mut tied = false;
 
     for &point in points {
-      let distance = self.manhattan_distance(point);
-      if min_distance.is_none() || distance < min_distance.unwrap() {
-        min_distance = Some(distance);
-        closest_point = Some(point);
-        tied = false;
-      } else if distance == min_distance.unwrap() {
-        tied = true;
+      let distance = self.distance(point, metric);
+      match closest {
+        Some((min_distance, _)) if distance > min_distance => {},
+        Some((min_distance, _)) if distance == min_distance => tied = true,
+        _ => {
+          closest = Some((distance, point));
+          tied = false;
+        }
       }
     }
 
-    if tied { None } else { closest_point }
+    if tied { None } else { closest.map(|(_, point)| point) }
   }
 
-  pub fn total_distance(self, points: &[Point]) -> i32 {
+  pub fn total_distance(self, points: &[Point<N>], metric: &dyn Metric) -> i64 {
     points.iter()
-      .map(|&point| self.manhattan_distance(point))
+      .map(|&point| self.distance(point, metric))
       .sum()
   }
 
-  pub fn manhattan_distance(self, other: Point) -> i32 {
-    let Point(x1, y1) = self;
-    let Point(x2, y2) = other;
+  pub fn distance(self, other: Point<N>, metric: &dyn Metric) -> i64 {
+    metric.distance(&self.0, &other.0)
+  }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ParsePointError {
+  InvalidNumber(ParseIntError),
+  WrongDimensions { expected: usize, found: usize }
+}
 
-    (x2 - x1).abs() + (y2 - y1).abs()
+impl fmt::Display for ParsePointError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      ParsePointError::InvalidNumber(error) =>
+        write!(f, "invalid coordinate: {}", error),
+      ParsePointError::WrongDimensions { expected, found } =>
+        write!(f, "expected {} coordinates, found {}", expected, found)
+    }
   }
 }
 
-impl FromStr for Point {
-  type Err = ParseIntError;
+impl<const N: usize> FromStr for Point<N> {
+  type Err = ParsePointError;
 
   fn from_str(string: &str) -> Result<Self, Self::Err> {
     let coords: Vec<i32> = string
-      .split(", ")
-      .map(|n| n.parse())
-      .collect::<Result<_, _>>()?;
+      .split(',')
+      .map(|n| n.trim().parse())
+      .collect::<Result<_, _>>()
+      .map_err(ParsePointError::InvalidNumber)?;
+
+    let found = coords.len();
+    coords.try_into()
+      .map(Point)
+      .map_err(|_| ParsePointError::WrongDimensions { expected: N, found })
+  }
+}
+
+fn bounding_box<const N: usize>(points: &[Point<N>]) -> (Point<N>, Point<N>) {
+  let mut min = [i32::MAX; N];
+  let mut max = [i32::MIN; N];
+
+  for &Point(coords) in points {
+    for axis in 0..N {
+      min[axis] = min[axis].min(coords[axis]);
+      max[axis] = max[axis].max(coords[axis]);
+    }
+  }
+
+  (Point(min), Point(max))
+}
+
+// Every point from `min` to `max`, both included, counting up along the
+// first axis first.
+fn cells<const N: usize>(Point(min): Point<N>, Point(max): Point<N>) -> impl Iterator<Item = Point<N>> {
+  let first = if (0..N).all(|axis| min[axis] <= max[axis]) { Some(Point(min)) } else { None };
+
+  iter::successors(first, move |&Point(mut coords)| {
+    for axis in 0..N {
+      if coords[axis] < max[axis] {
+        coords[axis] += 1;
+        return Some(Point(coords));
+      }
+      coords[axis] = min[axis];
+    }
+    None
+  })
+}
 
-    Ok(Point(coords[0], coords[1]))
+/// Like `Voronoi::areas`, but for any metric and number of dimensions, by
+/// checking every point in the bounding box against every coordinate. As in
+/// the puzzle, areas that reach the edge of the box count as infinite.
+fn areas_in_box<const N: usize>(points: &[Point<N>], metric: &dyn Metric) -> Vec<Option<u32>> {
+  let (min, max) = bounding_box(points);
+  let mut areas = vec![Some(0); points.len()];
+
+  for cell in cells(min, max) {
+    if let Some(closest) = cell.closest(points, metric) {
+      let on_edge = (0..N).any(|axis| cell.0[axis] == min.0[axis] || cell.0[axis] == max.0[axis]);
+      for (area, _) in areas.iter_mut().zip(points.iter()).filter(|&(_, &point)| point == closest) {
+        *area = if on_edge { None } else { area.map(|area| area + 1) };
+      }
+    }
   }
+
+  areas
+}
+
+/// The number of points in the bounding box less than `threshold` away from
+/// all the coordinates put together.
+fn safe_area_in_box<const N: usize>(points: &[Point<N>], metric: &dyn Metric, threshold: i64) -> usize {
+  let (min, max) = bounding_box(points);
+
+  cells(min, max)
+    .filter(|cell| cell.total_distance(points, metric) < threshold)
+    .count()
 }
 
-pub fn solve(input_file: File) {
+pub fn solve(input_file: File, options: &[String]) {
+  let mut threshold = 10_000;
+  let mut metric: Box<dyn Metric> = Box::new(metric::Manhattan);
+  let mut maps = Vec::new();
+
+  for option in options.iter() {
+    match options::split(option) {
+      ("map", path) => maps.push(path),
+      ("threshold", Some(n)) => threshold = n.parse().expect("--threshold takes a number"),
+      ("metric", Some(name)) => metric = metric::by_name(name).unwrap_or_else(|| {
+        let names: Vec<&str> = metric::all().iter().map(|m| m.name()).collect();
+        panic!("Unknown metric {}, expected one of: {}", name, names.join(", "))
+      }),
+      _ => panic!("Unknown option for day 6: {}", option)
+    }
+  }
+
   let reader = BufReader::new(input_file);
 
-  let points: Vec<Point> = reader
+  let lines: Vec<String> = reader
     .lines()
-    .flatten()
-    .map(|line| line.parse())
-    .flatten()
+    .map_while(Result::ok)
+    .filter(|line| !line.trim().is_empty())
     .collect();
 
-  let &left   = points.iter().map(|Point(x, _)| x).min().unwrap();
-  let &right  = points.iter().map(|Point(x, _)| x).max().unwrap();
-  let &top    = points.iter().map(|Point(_, y)| y).min().unwrap();
-  let &bottom = points.iter().map(|Point(_, y)| y).max().unwrap();
-
-  let mut area_by_point = HashMap::new();
-  let mut safe_area = 0;
-
-  for y in top..bottom {
-    for x in left..right {
-      if let Some(point) = Point(x, y).closest(&points) {
-        let area = area_by_point.entry(point).or_insert(0);
-        if x == left || x == right || y == top || y == bottom {
-          *area = -1;
-        } else if *area != -1 {
-          *area += 1;
-        }
+  let dimensions = lines.first().map_or(2, |line| line.split(',').count());
+
+  if dimensions != 2 && !maps.is_empty() {
+    panic!("Only coordinates with 2 dimensions can be mapped");
+  }
+
+  match (dimensions, metric.name()) {
+    (2, "manhattan") => {
+      let points: Vec<Point> = parse_points(&lines);
+
+      // Boxes too large to label at once are checked cell by cell instead.
+      let areas = match Voronoi::new(&points, 0) {
+        Ok(voronoi) => voronoi.areas(points.len()),
+        Err(_) => areas_in_box(&points, &*metric)
+      };
+      let max_area = areas
+        .into_iter()
+        .flatten()
+        .max();
+
+      match max_area {
+        Some(max_area) => println!("Max area: {}", max_area),
+        None => println!("Max area: every area is infinite")
       }
+      match safe::safe_region_size(&points, threshold) {
+        Ok(size) => println!("Safe area: {}", size),
+        Err(error) => println!("Safe area: {}", error)
+      }
+      draw_maps(&points, &*metric, threshold, &maps);
+    },
+    (2, _) => {
+      let points: Vec<Point> = parse_points(&lines);
+      solve_in_box(&points, &*metric, threshold);
+      draw_maps(&points, &*metric, threshold, &maps);
+    },
+    (3, _) => solve_in_box(&parse_points::<3>(&lines), &*metric, threshold),
+    (4, _) => solve_in_box(&parse_points::<4>(&lines), &*metric, threshold),
+    _ => panic!("Coordinates with {} dimensions aren't supported", dimensions)
+  }
+}
+
+fn draw_maps(points: &[Point], metric: &dyn Metric, threshold: i64, maps: &[Option<&str>]) {
+  if maps.is_empty() {
+    return;
+  }
 
-      if Point(x, y).total_distance(&points) < 10_000 {
-        safe_area += 1;
+  let territories = Territories::new(points, metric, threshold);
+
+  for map in maps.iter() {
+    match map {
+      None => print!("{}", territories.to_ascii()),
+      Some(path) => {
+        let mut file = File::create(path).expect("couldn't create map file");
+        territories.write_ppm(&mut file).expect("couldn't write map file");
       }
     }
   }
+}
 
-  let max_area = area_by_point.iter()
-    .map(|(_, area)| area)
-    .max()
-    .unwrap();
+fn parse_points<const N: usize>(lines: &[String]) -> Vec<Point<N>> {
+  lines.iter()
+    .enumerate()
+    .filter_map(|(index, line)| match line.parse() {
+      Ok(point) => Some(point),
+      Err(error) => {
+        eprintln!("Skipping line {}: {}", index + 1, error);
+        None
+      }
+    })
+    .collect()
+}
 
-  println!("Max area: {}", max_area);
-  println!("Safe area: {}", safe_area);
+fn solve_in_box<const N: usize>(points: &[Point<N>], metric: &dyn Metric, threshold: i64) {
+  let max_area = areas_in_box(points, metric)
+    .into_iter()
+    .flatten()
+    .max();
+
+  match max_area {
+    Some(max_area) => println!("Max area: {}", max_area),
+    None => println!("Max area: every area is infinite")
+  }
+  println!("Safe area within bounding box: {}", safe_area_in_box(points, metric, threshold));
+}
+
+/// The coordinates in the puzzle's example.
+#[cfg(test)]
+fn example_coords() -> Vec<Point> {
+  vec![
+    Point([1, 1]),
+    Point([1, 6]),
+    Point([8, 3]),
+    Point([3, 4]),
+    Point([5, 5]),
+    Point([8, 9]),
+  ]
 }
 
 #[cfg(test)]
 mod tests {
   use super::*;
+  use super::metric::*;
 
   #[test]
   fn test_point_parse() {
     assert_eq!(
       "192, 220".parse(),
-      Ok(Point(192, 220))
+      Ok(Point([192, 220]))
     );
   }
 
   #[test]
   fn test_point_closest() {
-    let coords = vec![
-      Point(1, 1),
-      Point(1, 6),
-      Point(8, 3),
-      Point(3, 4),
-      Point(5, 5),
-      Point(8, 9),
-    ];
+    let coords = example_coords();
 
     assert_eq!(
-      Point(4, 7).closest(&coords),
-      Some(Point(5, 5))
+      Point([4, 7]).closest(&coords, &Manhattan),
+      Some(Point([5, 5]))
     );
     assert_eq!(
-      Point(1, 1).closest(&coords),
-      Some(Point(1, 1))
+      Point([1, 1]).closest(&coords, &Manhattan),
+      Some(Point([1, 1]))
     );
     assert_eq!(
-      Point(5, 1).closest(&coords),
+      Point([5, 1]).closest(&coords, &Manhattan),
       None
     );
   }
 
   #[test]
   fn test_point_total_distance() {
-    let coords = vec![
-      Point(1, 1),
-      Point(1, 6),
-      Point(8, 3),
-      Point(3, 4),
-      Point(5, 5),
-      Point(8, 9),
-    ];
+    let coords = example_coords();
 
     assert_eq!(
-      Point(4, 3).total_distance(&coords),
+      Point([4, 3]).total_distance(&coords, &Manhattan),
       30
     );
   }
 
   #[test]
-  fn test_point_manhattan_distance() {
+  fn test_point_distance() {
     assert_eq!(
-      Point(4, 7).manhattan_distance(Point(2, 3)),
+      Point([4, 7]).distance(Point([2, 3]), &Manhattan),
       6
     );
+    assert_eq!(
+      Point([4, 7, 1, 0]).distance(Point([2, 3, 0, -2]), &Chebyshev),
+      4
+    );
+  }
+
+  #[test]
+  fn test_point_parse_dimensions() {
+    assert_eq!("1, 2, 3".parse(), Ok(Point([1, 2, 3])));
+    assert_eq!(
+      "1, 2, 3".parse::<Point>(),
+      Err(ParsePointError::WrongDimensions { expected: 2, found: 3 })
+    );
+    assert!(matches!("1, x".parse::<Point>(), Err(ParsePointError::InvalidNumber(_))));
+  }
+
+  #[test]
+  fn test_point_closest_in_3d() {
+    let coords = vec![Point([0, 0, 0]), Point([4, 0, 0]), Point([0, 0, 6])];
+
+    assert_eq!(Point([1, 2, 2]).closest(&coords, &Manhattan), Some(Point([0, 0, 0])));
+    assert_eq!(Point([2, 0, 0]).closest(&coords, &Manhattan), None);
+    assert_eq!(Point([3, 0, 1]).closest(&coords, &Chebyshev), Some(Point([4, 0, 0])));
+    assert_eq!(Point([2, 0, 4]).closest(&coords, &SquaredEuclidean), Some(Point([0, 0, 6])));
+    assert_eq!(Point([1, 1, 1]).total_distance(&coords, &SquaredEuclidean), 3 + 11 + 27);
+  }
+
+  #[test]
+  fn test_cells() {
+    let all: Vec<Point<3>> = cells(Point([0, 0, 0]), Point([1, 2, 1])).collect();
+
+    assert_eq!(all.len(), 12);
+    assert_eq!(all[..3], [Point([0, 0, 0]), Point([1, 0, 0]), Point([0, 1, 0])]);
+    assert_eq!(cells(Point([1]), Point([0])).count(), 0);
+  }
+
+  #[test]
+  fn test_areas_in_box_matches_voronoi() {
+    let coords = example_coords();
+
+    assert_eq!(areas_in_box(&coords, &Manhattan), Voronoi::new(&coords, 0).unwrap().areas(coords.len()));
+    assert_eq!(safe_area_in_box(&coords, &Manhattan, 32), 16);
   }
 }
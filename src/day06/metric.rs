@@ -0,0 +1,79 @@
+/// A way of measuring the distance between two points with the same number
+/// of coordinates.
+pub trait Metric {
+  fn name(&self) -> &'static str;
+
+  fn distance(&self, a: &[i32], b: &[i32]) -> i64;
+}
+
+fn differences<'a>(a: &'a [i32], b: &'a [i32]) -> impl Iterator<Item = i64> + 'a {
+  a.iter().zip(b.iter()).map(|(&a, &b)| (i64::from(a) - i64::from(b)).abs())
+}
+
+/// The sum of the differences along each axis, like the puzzle.
+pub struct Manhattan;
+
+impl Metric for Manhattan {
+  fn name(&self) -> &'static str { "manhattan" }
+
+  fn distance(&self, a: &[i32], b: &[i32]) -> i64 {
+    differences(a, b).sum()
+  }
+}
+
+/// The largest difference along any axis.
+pub struct Chebyshev;
+
+impl Metric for Chebyshev {
+  fn name(&self) -> &'static str { "chebyshev" }
+
+  fn distance(&self, a: &[i32], b: &[i32]) -> i64 {
+    differences(a, b).max().unwrap_or(0)
+  }
+}
+
+/// The square of the straight line distance, which orders points the same
+/// way without leaving the integers.
+pub struct SquaredEuclidean;
+
+impl Metric for SquaredEuclidean {
+  fn name(&self) -> &'static str { "squared-euclidean" }
+
+  fn distance(&self, a: &[i32], b: &[i32]) -> i64 {
+    differences(a, b).map(|d| d * d).sum()
+  }
+}
+
+pub fn all() -> Vec<Box<dyn Metric>> {
+  vec![
+    Box::new(Manhattan),
+    Box::new(Chebyshev),
+    Box::new(SquaredEuclidean)
+  ]
+}
+
+pub fn by_name(name: &str) -> Option<Box<dyn Metric>> {
+  all().into_iter().find(|metric| metric.name() == name)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_metrics() {
+    let (a, b) = ([4, 7, -1], [2, 3, 2]);
+
+    assert_eq!(Manhattan.distance(&a, &b), 9);
+    assert_eq!(Chebyshev.distance(&a, &b), 4);
+    assert_eq!(SquaredEuclidean.distance(&a, &b), 29);
+  }
+
+  #[test]
+  fn test_by_name() {
+    for metric in all() {
+      assert_eq!(by_name(metric.name()).unwrap().name(), metric.name());
+    }
+    assert!(by_name("nope").is_none());
+  }
+}
@@ -0,0 +1,154 @@
+use std::convert::TryFrom;
+use std::ops::Range;
+
+use super::Point;
+
+// The total distance from each of `values` to every one of `coords` along a
+// single axis, for every value in `values`.
+fn axis_sums(coords: &[i32], values: Range<i32>) -> Vec<i64> {
+  let mut coords = coords.to_vec();
+  coords.sort();
+
+  let mut sum: i64 = coords.iter().map(|&c| (i64::from(c) - i64::from(values.start)).abs()).sum();
+  let mut at_or_before = 0;
+  let mut sums = Vec::with_capacity(values.len());
+
+  for value in values {
+    while at_or_before < coords.len() && coords[at_or_before] <= value {
+      at_or_before += 1;
+    }
+    sums.push(sum);
+
+    // Stepping one to the right moves away from the coordinates at or
+    // before `value` and towards the rest.
+    sum += at_or_before as i64 - (coords.len() - at_or_before) as i64;
+  }
+
+  sums
+}
+
+// Sorts values that only go down and then only go up, by merging the two
+// halves, which are already sorted.
+fn sort_convex(values: &[i64]) -> Vec<i64> {
+  let lowest = (0..values.len()).min_by_key(|&i| values[i]).unwrap_or(0);
+  let mut falling = values[..lowest].iter().rev().peekable();
+  let mut rising = values[lowest..].iter().peekable();
+  let mut sorted = Vec::with_capacity(values.len());
+
+  loop {
+    let next = match (falling.peek(), rising.peek()) {
+      (Some(&&a), Some(&&b)) => if a <= b { falling.next() } else { rising.next() },
+      (Some(_), None) => falling.next(),
+      (None, Some(_)) => rising.next(),
+      (None, None) => break
+    };
+    sorted.push(*next.unwrap());
+  }
+
+  sorted
+}
+
+/// Counts the cells whose total distance to all the points is less than
+/// `threshold`, wherever they are. The total distance is the sum of the
+/// distances along each axis, so those are computed separately. A cell more
+/// than `threshold / n` beyond the points' bounding box is at least
+/// `threshold` away in total, so only the box grown by that much is checked.
+/// Fails if that box doesn't fit in `i32` coordinates.
+pub fn safe_region_size(points: &[Point], threshold: i64) -> Result<u64, String> {
+  if points.is_empty() || threshold <= 0 {
+    return Ok(0);
+  }
+
+  let too_large = || format!("threshold {} is too large for these coordinates", threshold);
+
+  let margin = i32::try_from((threshold as u64).div_ceil(points.len() as u64)).map_err(|_| too_large())?;
+  let xs: Vec<i32> = points.iter().map(|&Point([x, _])| x).collect();
+  let ys: Vec<i32> = points.iter().map(|&Point([_, y])| y).collect();
+
+  let range = |coords: &[i32]| -> Result<Range<i32>, String> {
+    let start = coords.iter().min().unwrap().checked_sub(margin);
+    let end = coords.iter().max().unwrap().checked_add(margin).and_then(|end| end.checked_add(1));
+    match (start, end) {
+      (Some(start), Some(end)) => Ok(start..end),
+      _ => Err(too_large())
+    }
+  };
+
+  let x_sums = sort_convex(&axis_sums(&xs, range(&xs)?));
+  let y_sums = sort_convex(&axis_sums(&ys, range(&ys)?));
+
+  // As the distance along x grows, fewer distances along y fit under the
+  // threshold, so the number that do only ever goes down.
+  let mut fitting = y_sums.len();
+  let mut size = 0;
+
+  for x_sum in x_sums {
+    while fitting > 0 && x_sum + y_sums[fitting - 1] >= threshold {
+      fitting -= 1;
+    }
+    if fitting == 0 {
+      break;
+    }
+    size += fitting as u64;
+  }
+
+  Ok(size)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use super::super::example_coords;
+  use super::super::metric::Manhattan;
+
+  fn brute_force(points: &[Point], threshold: i64) -> u64 {
+    let reach = threshold as i32 + 20;
+    let mut size = 0;
+
+    for y in -reach..reach {
+      for x in -reach..reach {
+        if Point([x, y]).total_distance(points, &Manhattan) < threshold {
+          size += 1;
+        }
+      }
+    }
+
+    size
+  }
+
+  #[test]
+  fn test_axis_sums() {
+    assert_eq!(axis_sums(&[1, 3, 3, 8], 0..10), vec![15, 11, 9, 7, 9, 11, 13, 15, 17, 21]);
+  }
+
+  #[test]
+  fn test_sort_convex() {
+    assert_eq!(sort_convex(&[9, 5, 2, 2, 4, 7, 11]), vec![2, 2, 4, 5, 7, 9, 11]);
+    assert_eq!(sort_convex(&[]), Vec::<i64>::new());
+  }
+
+  #[test]
+  fn test_safe_region_size() {
+    assert_eq!(safe_region_size(&example_coords(), 32), Ok(16));
+  }
+
+  #[test]
+  fn test_safe_region_size_threshold_too_large() {
+    assert_eq!(
+      safe_region_size(&example_coords(), 20_000_000_000),
+      Err("threshold 20000000000 is too large for these coordinates".to_string())
+    );
+    assert!(safe_region_size(&[Point([i32::MAX - 1, 0])], 5).is_err());
+  }
+
+  #[test]
+  fn test_safe_region_size_beyond_bounding_box() {
+    // Every cell of the box is safe, and so are plenty around it.
+    let points = vec![Point([0, 0]), Point([1, 1])];
+
+    for threshold in 0..12 {
+      assert_eq!(safe_region_size(&points, threshold), Ok(brute_force(&points, threshold)), "threshold {}", threshold);
+    }
+    assert_eq!(safe_region_size(&example_coords(), 60), Ok(brute_force(&example_coords(), 60)));
+  }
+}
@@ -0,0 +1,241 @@
+use std::io;
+use std::io::prelude::*;
+
+use super::{bounding_box, cells, Point};
+use super::metric::Metric;
+use super::voronoi::{Owner, Voronoi};
+
+const TIE: char = '.';
+const TIE_COLOR: [u8; 3] = [40, 40, 40];
+const COORDINATE_COLOR: [u8; 3] = [255, 255, 255];
+
+/// The closest coordinate to every cell of the coordinates' bounding box,
+/// plus a one cell margin, and which of those cells are in the safe region.
+/// With the Manhattan metric, the owners come from a `Voronoi` labelling
+/// when the box isn't too large for one.
+#[derive(Debug)]
+pub struct Territories {
+  min: Point,
+  width: usize,
+  height: usize,
+  points: Vec<Point>,
+  owners: Vec<Option<usize>>,
+  safe: Vec<bool>,
+  infinite: Vec<bool>
+}
+
+impl Territories {
+  pub fn new(points: &[Point], metric: &dyn Metric, threshold: i64) -> Self {
+    // Without coordinates there's no bounding box, and nothing to map.
+    if points.is_empty() {
+      return Territories {
+        min: Point([0, 0]),
+        width: 0,
+        height: 0,
+        points: Vec::new(),
+        owners: Vec::new(),
+        safe: Vec::new(),
+        infinite: Vec::new()
+      };
+    }
+
+    let (Point([left, top]), Point([right, bottom])) = bounding_box(points);
+    let (min, max) = (Point([left - 1, top - 1]), Point([right + 1, bottom + 1]));
+
+    let mut territories = Territories {
+      min,
+      width: (max.0[0] - min.0[0] + 1) as usize,
+      height: (max.0[1] - min.0[1] + 1) as usize,
+      points: points.to_vec(),
+      owners: Vec::new(),
+      safe: Vec::new(),
+      infinite: vec![false; points.len()]
+    };
+
+    let voronoi = if metric.name() == "manhattan" { Voronoi::new(points, 1).ok() } else { None };
+
+    let owners: Vec<Option<usize>> = if let Some(voronoi) = voronoi {
+      voronoi.labels().iter()
+        .map(|&label| match label {
+          Some(Owner::Coordinate(i)) => Some(i),
+          _ => None
+        })
+        .collect()
+    } else {
+      cells(min, max)
+        .map(|cell| cell.closest(points, metric).and_then(|closest| points.iter().position(|&point| point == closest)))
+        .collect()
+    };
+
+    for (cell, owner) in cells(min, max).zip(owners) {
+      // Outside the box, territories that reach the edge never stop.
+      let Point([x, y]) = cell;
+      if let Some(i) = owner.filter(|_| x == min.0[0] || x == max.0[0] || y == min.0[1] || y == max.0[1]) {
+        territories.infinite[i] = true;
+      }
+
+      territories.owners.push(owner);
+      territories.safe.push(cell.total_distance(points, metric) < threshold);
+    }
+
+    territories
+  }
+
+  /// Renders the map like the puzzle does: a letter for each coordinate's
+  /// territory, uppercase at the coordinate itself, and `.` where coordinates
+  /// tie. Letters start over after `z`.
+  pub fn to_ascii(&self) -> String {
+    let mut ascii = String::with_capacity((self.width + 1) * self.height);
+
+    for (index, owner) in self.owners.iter().enumerate() {
+      ascii.push(match *owner {
+        Some(i) => {
+          let letter = (b'a' + (i % 26) as u8) as char;
+          if self.is_coordinate(index, i) { letter.to_ascii_uppercase() } else { letter }
+        },
+        None => TIE
+      });
+
+      if (index + 1) % self.width == 0 {
+        ascii.push('\n');
+      }
+    }
+
+    ascii
+  }
+
+  /// Writes a binary PPM image with a color for each coordinate's territory,
+  /// dimmed if it's infinite. Ties are dark gray, the coordinates themselves
+  /// white, and the safe region is washed out towards white.
+  pub fn write_ppm<W: Write>(&self, out: &mut W) -> io::Result<()> {
+    write!(out, "P6\n{} {}\n255\n", self.width, self.height)?;
+
+    let pixels: Vec<u8> = self.owners.iter()
+      .enumerate()
+      .flat_map(|(index, owner)| {
+        let color = match *owner {
+          Some(i) if self.is_coordinate(index, i) => COORDINATE_COLOR,
+          Some(i) => color(i, self.infinite[i]),
+          None => TIE_COLOR
+        };
+
+        if self.safe[index] {
+          color.map(|c| ((u16::from(c) + 255) / 2) as u8)
+        } else {
+          color
+        }
+      })
+      .collect();
+
+    out.write_all(&pixels)
+  }
+
+  fn is_coordinate(&self, index: usize, owner: usize) -> bool {
+    let Point([x, y]) = self.points[owner];
+    let (column, row) = ((x - self.min.0[0]) as usize, (y - self.min.0[1]) as usize);
+
+    index == row * self.width + column
+  }
+}
+
+// Spreads the coordinates' colors around the color wheel by the golden
+// ratio, so neighbors in the input don't end up looking alike.
+fn color(i: usize, infinite: bool) -> [u8; 3] {
+  let hue = (i as f64 * 0.618_034).fract() * 6.0;
+  let value = if infinite { 110.0 } else { 230.0 };
+  let low = value * 0.35;
+  let rising = low + (value - low) * hue.fract();
+  let falling = value - (value - low) * hue.fract();
+
+  let [r, g, b] = match hue as u32 {
+    0 => [value, rising, low],
+    1 => [falling, value, low],
+    2 => [low, value, rising],
+    3 => [low, falling, value],
+    4 => [rising, low, value],
+    _ => [value, low, falling]
+  };
+
+  [r as u8, g as u8, b as u8]
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use super::super::example_coords;
+  use super::super::metric::Manhattan;
+
+  #[test]
+  fn test_to_ascii() {
+    let ascii = Territories::new(&example_coords(), &Manhattan, 32).to_ascii();
+    let lines: Vec<&str> = ascii.lines().collect();
+
+    assert_eq!(lines.len(), 11);
+    assert_eq!(
+      lines[..10],
+      [
+        "aaaaa.cccc",
+        "aAaaa.cccc",
+        "aaaddecccc",
+        "aadddeccCc",
+        "..dDdeeccc",
+        "bb.deEeecc",
+        "bBb.eeee..",
+        "bbb.eeefff",
+        "bbb.eeffff",
+        "bbb.ffffFf",
+      ]
+    );
+  }
+
+  #[test]
+  fn test_manhattan_owners_match_closest() {
+    let coords = example_coords();
+    let territories = Territories::new(&coords, &Manhattan, 32);
+    let Point([left, top]) = territories.min;
+    let max = Point([left + territories.width as i32 - 1, top + territories.height as i32 - 1]);
+
+    let expected: Vec<Option<usize>> = cells(territories.min, max)
+      .map(|cell| cell.closest(&coords, &Manhattan).and_then(|closest| coords.iter().position(|&p| p == closest)))
+      .collect();
+
+    assert_eq!(territories.owners, expected);
+  }
+
+  #[test]
+  fn test_infinite() {
+    let territories = Territories::new(&example_coords(), &Manhattan, 32);
+
+    assert_eq!(territories.infinite, vec![true, true, true, false, false, true]);
+  }
+
+  #[test]
+  fn test_no_coordinates() {
+    let territories = Territories::new(&[], &Manhattan, 32);
+    let mut ppm = Vec::new();
+    territories.write_ppm(&mut ppm).unwrap();
+
+    assert_eq!(territories.to_ascii(), "");
+    assert_eq!(ppm, b"P6\n0 0\n255\n");
+  }
+
+  #[test]
+  fn test_write_ppm() {
+    let territories = Territories::new(&example_coords(), &Manhattan, 32);
+    let mut ppm = Vec::new();
+    territories.write_ppm(&mut ppm).unwrap();
+
+    let header = b"P6\n10 11\n255\n";
+    assert_eq!(&ppm[..header.len()], header);
+    assert_eq!(ppm.len(), header.len() + 10 * 11 * 3);
+
+    let pixel = |x: usize, y: usize| {
+      let start = header.len() + (y * 10 + x) * 3;
+      [ppm[start], ppm[start + 1], ppm[start + 2]]
+    };
+    // A coordinate outside the safe region, a tie, and the safe region.
+    assert_eq!(pixel(1, 1), COORDINATE_COLOR);
+    assert_eq!(pixel(5, 0), TIE_COLOR);
+    assert_eq!(pixel(4, 3), color(3, false).map(|c| ((u16::from(c) + 255) / 2) as u8));
+  }
+}
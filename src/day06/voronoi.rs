@@ -0,0 +1,202 @@
+use std::collections::VecDeque;
+use std::convert::TryFrom;
+
+use super::Point;
+
+// The most cells `Voronoi::new` will label, which keeps its grids to a few
+// gigabytes.
+const MAX_CELLS: usize = 1 << 26;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Owner {
+  /// The index of the only closest coordinate.
+  Coordinate(usize),
+  /// More than one coordinate is closest.
+  Tie
+}
+
+/// Which coordinate is closest to each cell of the coordinates' bounding box,
+/// border included, grown by a margin.
+#[derive(Debug)]
+pub struct Voronoi {
+  left: i32,
+  top: i32,
+  width: usize,
+  height: usize,
+  owners: Vec<Option<Owner>>
+}
+
+impl Voronoi {
+  /// Labels every cell with a breadth-first search from all the coordinates
+  /// at once. Within the box, the cells at distance `d + 1` from the nearest
+  /// coordinate are reached from the cells at distance `d`, so a cell is tied
+  /// exactly when it's reached from different owners, or from a tie. That
+  /// holds for any box around all the coordinates, so it works with a
+  /// `margin` too. Fails if the box has too many cells to label, or if it
+  /// and its neighbouring cells don't fit in `i32` coordinates.
+  pub fn new(points: &[Point], margin: i32) -> Result<Self, String> {
+    let margin = i64::from(margin);
+    let left = points.iter().map(|&Point([x, _])| i64::from(x) - margin).min().unwrap_or(0);
+    let right = points.iter().map(|&Point([x, _])| i64::from(x) + margin).max().unwrap_or(-1);
+    let top = points.iter().map(|&Point([_, y])| i64::from(y) - margin).min().unwrap_or(0);
+    let bottom = points.iter().map(|&Point([_, y])| i64::from(y) + margin).max().unwrap_or(-1);
+
+    let too_large = || format!("the box from ({}, {}) to ({}, {}) is too large to label", left, top, right, bottom);
+    let fits = [left - 1, right + 1, top - 1, bottom + 1].iter().all(|&edge| i32::try_from(edge).is_ok());
+    let width = usize::try_from(right - left + 1).map_err(|_| too_large())?;
+    let height = usize::try_from(bottom - top + 1).map_err(|_| too_large())?;
+    let num_cells = width.checked_mul(height)
+      .filter(|&num_cells| fits && num_cells <= MAX_CELLS)
+      .ok_or_else(too_large)?;
+
+    let mut voronoi = Voronoi {
+      left: left as i32,
+      top: top as i32,
+      width,
+      height,
+      owners: vec![None; num_cells]
+    };
+
+    let mut distances = vec![u32::MAX; voronoi.owners.len()];
+    let mut queue = VecDeque::new();
+
+    for (i, &point) in points.iter().enumerate() {
+      let index = voronoi.index(point).unwrap();
+      if distances[index] == 0 {
+        voronoi.owners[index] = Some(Owner::Tie);
+      } else {
+        distances[index] = 0;
+        voronoi.owners[index] = Some(Owner::Coordinate(i));
+        queue.push_back(point);
+      }
+    }
+
+    while let Some(point) = queue.pop_front() {
+      let index = voronoi.index(point).unwrap();
+      let (distance, owner) = (distances[index], voronoi.owners[index]);
+      let Point([x, y]) = point;
+
+      for &neighbor in &[Point([x - 1, y]), Point([x + 1, y]), Point([x, y - 1]), Point([x, y + 1])] {
+        let neighbor_index = match voronoi.index(neighbor) {
+          Some(neighbor_index) => neighbor_index,
+          None => continue
+        };
+
+        if distances[neighbor_index] == u32::MAX {
+          distances[neighbor_index] = distance + 1;
+          voronoi.owners[neighbor_index] = owner;
+          queue.push_back(neighbor);
+        } else if distances[neighbor_index] == distance + 1 && voronoi.owners[neighbor_index] != owner {
+          voronoi.owners[neighbor_index] = Some(Owner::Tie);
+        }
+      }
+    }
+
+    Ok(voronoi)
+  }
+
+  fn index(&self, Point([x, y]): Point) -> Option<usize> {
+    let (column, row) = (x - self.left, y - self.top);
+
+    if column < 0 || row < 0 || column as usize >= self.width || row as usize >= self.height {
+      None
+    } else {
+      Some(row as usize * self.width + column as usize)
+    }
+  }
+
+  /// The labels, row by row from the top left corner of the box.
+  pub fn labels(&self) -> &[Option<Owner>] {
+    &self.owners
+  }
+
+  /// The number of cells closest to each coordinate, or `None` for those
+  /// whose area is infinite. Beyond the box, every cell is closest to the
+  /// same coordinates as the nearest cell on the border, so those are the
+  /// ones with infinite areas.
+  pub fn areas(&self, num_points: usize) -> Vec<Option<u32>> {
+    let mut areas = vec![Some(0); num_points];
+
+    for (index, owner) in self.owners.iter().enumerate() {
+      if let Some(Owner::Coordinate(i)) = *owner {
+        let (column, row) = (index % self.width, index / self.width);
+        let on_border = column == 0 || row == 0 || column == self.width - 1 || row == self.height - 1;
+
+        areas[i] = if on_border { None } else { areas[i].map(|area| area + 1) };
+      }
+    }
+
+    areas
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use super::super::example_coords;
+  use super::super::metric::Manhattan;
+
+  #[test]
+  fn test_voronoi_areas() {
+    let coords = example_coords();
+
+    assert_eq!(
+      Voronoi::new(&coords, 0).unwrap().areas(coords.len()),
+      vec![None, None, None, Some(9), Some(17), None]
+    );
+    assert_eq!(
+      Voronoi::new(&coords, 3).unwrap().areas(coords.len()),
+      vec![None, None, None, Some(9), Some(17), None]
+    );
+  }
+
+  #[test]
+  fn test_voronoi_matches_closest() {
+    let mut coords = example_coords();
+    coords.extend((0..30).map(|i| Point([i * 37 % 23, i * 11 % 17])));
+    coords.push(Point([4, 4]));
+
+    for &margin in &[0, 2] {
+      let voronoi = Voronoi::new(&coords, margin).unwrap();
+
+      for (index, &label) in voronoi.labels().iter().enumerate() {
+        let (column, row) = ((index % voronoi.width) as i32, (index / voronoi.width) as i32);
+        let cell = Point([voronoi.left + column, voronoi.top + row]);
+        let expected = match cell.closest(&coords, &Manhattan) {
+          Some(point) => Some(Owner::Coordinate(coords.iter().position(|&p| p == point).unwrap())),
+          None => Some(Owner::Tie)
+        };
+        assert_eq!(label, expected, "at {:?} with margin {}", cell, margin);
+      }
+    }
+  }
+
+  #[test]
+  fn test_voronoi_flags_regions_on_the_border() {
+    // The last coordinate only reaches the border in the rightmost column.
+    let coords = vec![Point([0, 0]), Point([0, 4]), Point([4, 2])];
+
+    assert_eq!(Voronoi::new(&coords, 0).unwrap().areas(coords.len()), vec![None, None, None]);
+  }
+
+  #[test]
+  fn test_voronoi_labels() {
+    let voronoi = Voronoi::new(&[Point([0, 0]), Point([2, 0])], 0).unwrap();
+
+    assert_eq!((voronoi.width, voronoi.height), (3, 1));
+    assert_eq!(
+      voronoi.labels(),
+      &[Some(Owner::Coordinate(0)), Some(Owner::Tie), Some(Owner::Coordinate(1))]
+    );
+  }
+
+  #[test]
+  fn test_voronoi_box_too_large() {
+    assert_eq!(
+      Voronoi::new(&[Point([0, 0]), Point([60000, 60000])], 0).unwrap_err(),
+      "the box from (0, 0) to (60000, 60000) is too large to label"
+    );
+    assert!(Voronoi::new(&[Point([i32::MAX, 0])], 0).is_err());
+    assert!(Voronoi::new(&[Point([i32::MAX - 1, 0])], 0).is_ok());
+  }
+}
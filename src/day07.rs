@@ -1,13 +1,30 @@
 use regex::Regex;
+use std::cmp::Reverse;
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::fs::File;
 use std::io::BufReader;
 use std::io::prelude::*;
+use std::process;
+
+use crate::options;
+
+mod critical;
+mod duration;
+mod graph;
+mod schedule;
+mod validate;
+
+use self::duration::Durations;
+use self::schedule::{Assignment, Schedule};
+use self::validate::ValidationError;
+
+/// The steps each step has to wait for, keyed by step name.
+pub type Dependencies = HashMap<String, HashSet<String>>;
 
 #[derive(Debug)]
 struct Worker {
-  step: Option<char>,
+  step: Option<String>,
   time_left: i32
 }
 
@@ -16,13 +33,11 @@ impl Worker {
     Worker { step: None, time_left: 0 }
   }
 
-  pub fn work(&mut self) -> Option<char> {
+  pub fn work(&mut self) -> Option<String> {
     if self.has_work() {
       self.time_left -= 1;
       if self.time_left == 0 {
-        let done = self.step;
-        self.step = None;
-        return done;
+        return self.step.take();
       }
     }
 
@@ -33,44 +48,206 @@ impl Worker {
     self.step.is_some()
   }
 
-  pub fn start(&mut self, step: char) {
+  pub fn start(&mut self, step: String, duration: i32) {
     self.step = Some(step);
-    self.time_left = 61 + (step as i32) - i32::from(b'A');
+    self.time_left = duration;
   }
 }
 
-pub fn solve(input_file: File) {
-  let rule_regex = Regex::new(
-    r"^Step ([A-Z]) must be finished before step ([A-Z]) can begin.$"
-  ).unwrap();
+/// Which available step a free worker takes first.
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Policy {
+  /// The first one alphabetically, like in the puzzle.
+  Alphabetical,
+  /// The one with the longest chain of steps still to do after it.
+  LongestRemainingPath
+}
+
+impl Policy {
+  pub fn name(self) -> &'static str {
+    match self {
+      Policy::Alphabetical => "alphabetical",
+      Policy::LongestRemainingPath => "longest-path"
+    }
+  }
+}
 
-  let mut dependencies: HashMap<char, HashSet<char>> = HashMap::new();
+/// Writes out steps in order: run together when they're all single letters
+/// like in the puzzle, otherwise separated by commas.
+pub fn join_steps(steps: &[String]) -> String {
+  if steps.iter().all(|step| step.chars().count() == 1) {
+    steps.concat()
+  } else {
+    steps.join(", ")
+  }
+}
+
+pub fn solve(input_file: File, options: &[String]) {
+  let mut num_workers = 5;
+  let mut durations = Durations::Alphabet { step_time: 60 };
+  let mut show_table = false;
+  let mut show_gantt = false;
+  let mut show_critical_path = false;
+  let mut policy = Policy::Alphabetical;
+  let mut dot: Option<(bool, bool)> = None;
+
+  for option in options.iter() {
+    match options::split(option) {
+      ("workers", Some(n)) => {
+        num_workers = n.parse().expect("--workers takes a number");
+        assert!(num_workers > 0, "--workers needs at least one worker");
+      },
+      ("step-time", Some(n)) => {
+        let step_time = n.parse().expect("--step-time takes a number");
+        assert!(step_time >= 0, "--step-time can't be negative");
+        durations = Durations::Alphabet { step_time };
+      },
+      ("durations", Some(file)) => {
+        let table = std::fs::read_to_string(file).expect("Couldn't read the durations file");
+        durations = table.parse().unwrap_or_else(|error| panic!("Invalid durations file {}: {}", file, error));
+      },
+      ("schedule", None) => show_table = true,
+      ("gantt", None) => show_gantt = true,
+      ("critical-path", None) => show_critical_path = true,
+      ("policy", Some("alphabetical")) => policy = Policy::Alphabetical,
+      ("policy", Some("longest-path")) => policy = Policy::LongestRemainingPath,
+      ("dot", None) => dot = Some((false, false)),
+      ("dot", Some(extras)) => {
+        let extras: Vec<&str> = extras.split(',').collect();
+        assert!(
+          extras.iter().all(|&extra| extra == "reduced" || extra == "schedule"),
+          "--dot takes `reduced`, `schedule` or both: {}", option
+        );
+        dot = Some((extras.contains(&"reduced"), extras.contains(&"schedule")));
+      },
+      _ => panic!("Unknown option for day 7: {}", option)
+    }
+  }
 
   let reader = BufReader::new(input_file);
-  for line in reader.lines() {
-    if let Some(caps) = rule_regex.captures(&line.unwrap()) {
-      let dependency: char = caps.get(1).unwrap().as_str().chars().next().unwrap();
-      let step: char = caps.get(2).unwrap().as_str().chars().next().unwrap();
+  let (dependencies, errors) = parse_dependencies(reader);
 
-      dependencies.entry(step).or_default().insert(dependency);
-      dependencies.entry(dependency).or_default();
+  let mut missing: Vec<&String> = dependencies.keys().filter(|step| durations.get(step).is_none()).collect();
+  missing.sort();
+  let errors: Vec<ValidationError> = errors.into_iter()
+    .chain(missing.into_iter().map(|step| ValidationError::MissingDuration(step.clone())))
+    .collect();
+
+  if !errors.is_empty() {
+    for error in errors.iter() {
+      eprintln!("{}", error);
     }
+    process::exit(1);
   }
 
-  let (step_order, _) = simulate(1, &dependencies);
-  let (_, seconds) = simulate(5, &dependencies);
+  let duration = |step: &str| durations.get(step).unwrap();
+  let result = simulate(1, Policy::Alphabetical, &dependencies, duration)
+    .and_then(|alone| Ok((alone, simulate(num_workers, policy, &dependencies, duration)?)));
+
+  match result {
+    Ok((alone, schedule)) => {
+      println!("Step order (1 worker): {}", join_steps(&alone.order));
+      println!("Time to complete ({} workers): {}", num_workers, schedule.duration);
 
-  println!("Step order (1 worker): {}", step_order);
-  println!("Time to complete (5 workers): {}", seconds);
+      if show_table {
+        println!();
+        print!("{}", schedule.to_table());
+      }
+      if show_gantt {
+        println!();
+        print!("{}", schedule.to_gantt(100));
+      }
+      if show_critical_path {
+        println!();
+        print_critical_path(num_workers, &dependencies, duration);
+      }
+      if let Some((reduced, annotated)) = dot {
+        let shown = if reduced { graph::transitive_reduction(&dependencies) } else { dependencies.clone() };
+        println!();
+        print!("{}", graph::to_dot(&shown, if annotated { Some(&schedule) } else { None }));
+      }
+    },
+    Err(error) => {
+      eprintln!("{}", error);
+      process::exit(1);
+    }
+  }
 }
 
-fn simulate(num_workers: i32, dependencies: &HashMap<char, HashSet<char>>) -> (String, i32) {
+fn print_critical_path<F: Fn(&str) -> i32>(num_workers: usize, dependencies: &Dependencies, duration: F) {
+  let critical = critical::critical_path(dependencies, &duration);
+
+  println!("Critical path: {} ({} seconds)", critical.path.join(" -> "), critical.length);
+  for policy in [Policy::Alphabetical, Policy::LongestRemainingPath].iter() {
+    let schedule = simulate(num_workers, *policy, dependencies, &duration).unwrap();
+    println!("Time to complete ({} workers, {} policy): {}", num_workers, policy.name(), schedule.duration);
+  }
+
+  println!();
+  let step_width = critical.timings.iter().map(|timing| timing.step.len()).max().unwrap_or(0).max(4);
+  println!("{:>width$}  Earliest start  Latest start  Slack", "Step", width = step_width);
+  for timing in critical.timings.iter() {
+    println!(
+      "{:>width$}  {:>14}  {:>12}  {:>5}",
+      timing.step, timing.earliest_start, timing.latest_start, timing.slack(), width = step_width
+    );
+  }
+}
+
+/// Reads the rules, along with an error for every non-blank line that isn't
+/// one.
+fn parse_dependencies<R: BufRead>(reader: R) -> (Dependencies, Vec<ValidationError>) {
+  let rule_regex = Regex::new(
+    r"^Step (\S+) must be finished before step (\S+) can begin\.$"
+  ).unwrap();
+
+  let mut dependencies = Dependencies::new();
+  let mut errors = Vec::new();
+
+  for (index, line) in reader.lines().enumerate() {
+    let line = line.unwrap();
+
+    if let Some(caps) = rule_regex.captures(&line) {
+      let dependency = caps.get(1).unwrap().as_str();
+      let step = caps.get(2).unwrap().as_str();
+
+      dependencies.entry(step.to_string()).or_default().insert(dependency.to_string());
+      dependencies.entry(dependency.to_string()).or_default();
+    } else if !line.trim().is_empty() {
+      errors.push(ValidationError::Unparseable { line: index + 1, text: line });
+    }
+  }
+
+  (dependencies, errors)
+}
+
+/// Runs the steps with `num_workers` workers, each free worker taking the
+/// first available step according to `policy` and spending `duration(step)`
+/// seconds on it. Steps that depend on each other in a cycle could never
+/// finish, so that's an error.
+fn simulate<F: Fn(&str) -> i32>(
+  num_workers: usize,
+  policy: Policy,
+  dependencies: &Dependencies,
+  duration: F
+) -> Result<Schedule, ValidationError> {
+  if let Some(cycle) = validate::find_cycle(dependencies) {
+    return Err(ValidationError::Cycle(cycle));
+  }
+
   let mut workers: Vec<Worker> = (0..num_workers).map(|_| Worker::new()).collect();
 
-  let mut todo: Vec<char> = dependencies.keys().cloned().collect();
-  todo.sort();
+  let mut todo: Vec<&str> = dependencies.keys().map(String::as_str).collect();
+  match policy {
+    Policy::Alphabetical => todo.sort(),
+    Policy::LongestRemainingPath => {
+      let lengths = critical::remaining_path_lengths(dependencies, &duration);
+      todo.sort_by_key(|&step| (Reverse(lengths[step]), step));
+    }
+  }
 
-  let mut done = String::new();
+  let mut done: Vec<String> = Vec::new();
+  let mut assignments = Vec::new();
   let num_steps = todo.len();
 
   let mut time = 0;
@@ -81,12 +258,15 @@ fn simulate(num_workers: i32, dependencies: &HashMap<char, HashSet<char>>) -> (S
       }
     }
 
-    for worker in workers.iter_mut() {
+    for (index, worker) in workers.iter_mut().enumerate() {
       if !worker.has_work() {
         let mut i = 0;
         while i < todo.len() {
-          if dependencies[&todo[i]].iter().all(|&step| done.find(|x| x == step).is_some()) {
-            worker.start(todo[i]);
+          if dependencies[todo[i]].iter().all(|step| done.contains(step)) {
+            let step = todo[i].to_string();
+            let duration = duration(&step);
+            assignments.push(Assignment { worker: index, step: step.clone(), start: time, end: time + duration });
+            worker.start(step, duration);
             todo.remove(i);
             break;
           }
@@ -98,5 +278,201 @@ fn simulate(num_workers: i32, dependencies: &HashMap<char, HashSet<char>>) -> (S
     time += 1;
   }
 
-  (done, time - 1)
+  Ok(Schedule { num_workers, assignments, order: done, duration: (time - 1).max(0) })
+}
+
+/// The dependencies in the puzzle's example, as `(dependency, step)` pairs.
+#[cfg(test)]
+const EXAMPLE_RULES: &[(&str, &str)] = &[
+  ("C", "A"), ("C", "F"), ("A", "B"), ("A", "D"), ("B", "E"), ("D", "E"), ("F", "E")
+];
+
+/// Builds dependencies from `(dependency, step)` pairs, for tests.
+#[cfg(test)]
+fn dependencies_from(rules: &[(&str, &str)]) -> Dependencies {
+  let mut dependencies = Dependencies::new();
+  for &(dependency, step) in rules {
+    dependencies.entry(step.to_string()).or_default().insert(dependency.to_string());
+    dependencies.entry(dependency.to_string()).or_default();
+  }
+  dependencies
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const EXAMPLE: &str = "\
+Step C must be finished before step A can begin.
+Step C must be finished before step F can begin.
+Step A must be finished before step B can begin.
+Step A must be finished before step D can begin.
+Step B must be finished before step E can begin.
+Step D must be finished before step E can begin.
+Step F must be finished before step E can begin.
+";
+
+  fn puzzle_duration(step: &str) -> i32 {
+    Durations::Alphabet { step_time: 0 }.get(step).unwrap()
+  }
+
+  #[test]
+  fn test_parse_dependencies() {
+    let (dependencies, errors) = parse_dependencies(EXAMPLE.as_bytes());
+
+    assert_eq!(dependencies, dependencies_from(EXAMPLE_RULES));
+    assert_eq!(errors, vec![]);
+  }
+
+  #[test]
+  fn test_parse_dependencies_reports_unparseable_lines() {
+    let input = "Step C must be finished before step A can begin.\n\nStep 1 must be finished first.\n";
+    let (dependencies, errors) = parse_dependencies(input.as_bytes());
+
+    assert_eq!(dependencies.len(), 2);
+    assert_eq!(
+      errors,
+      vec![ValidationError::Unparseable { line: 3, text: "Step 1 must be finished first.".to_string() }]
+    );
+  }
+
+  #[test]
+  fn test_simulate() {
+    let dependencies = dependencies_from(EXAMPLE_RULES);
+
+    assert_eq!(join_steps(&simulate(1, Policy::Alphabetical, &dependencies, puzzle_duration).unwrap().order), "CABDFE");
+
+    let schedule = simulate(2, Policy::Alphabetical, &dependencies, puzzle_duration).unwrap();
+    assert_eq!(join_steps(&schedule.order), "CABFDE");
+    assert_eq!(schedule.duration, 15);
+    assert_eq!(
+      schedule.assignments[..3],
+      [
+        Assignment { worker: 0, step: "C".to_string(), start: 0, end: 3 },
+        Assignment { worker: 0, step: "A".to_string(), start: 3, end: 4 },
+        Assignment { worker: 1, step: "F".to_string(), start: 3, end: 9 },
+      ]
+    );
+  }
+
+  #[test]
+  fn test_schedule_to_table() {
+    let dependencies = dependencies_from(EXAMPLE_RULES);
+    let schedule = simulate(2, Policy::Alphabetical, &dependencies, puzzle_duration).unwrap();
+
+    assert_eq!(
+      schedule.to_table(),
+      "\
+Second   Worker 1   Worker 2   Done
+   0        C          .
+   1        C          .
+   2        C          .
+   3        A          F       C
+   4        B          F       CA
+   5        B          F       CA
+   6        D          F       CAB
+   7        D          F       CAB
+   8        D          F       CAB
+   9        D          .       CABF
+  10        E          .       CABFD
+  11        E          .       CABFD
+  12        E          .       CABFD
+  13        E          .       CABFD
+  14        E          .       CABFD
+  15        .          .       CABFDE
+"
+    );
+  }
+
+  #[test]
+  fn test_schedule_to_gantt() {
+    let dependencies = dependencies_from(EXAMPLE_RULES);
+    let schedule = simulate(2, Policy::Alphabetical, &dependencies, puzzle_duration).unwrap();
+
+    assert_eq!(
+      schedule.to_gantt(80),
+      "\
+Worker 1  CCCABBDDDDEEEEE
+Worker 2  ...FFFFFF......
+          0         10
+"
+    );
+    assert_eq!(
+      schedule.to_gantt(5),
+      "\
+3 seconds per column
+Worker 1  CADDE
+Worker 2  .FF..
+          0
+"
+    );
+  }
+
+  #[test]
+  fn test_simulate_without_steps() {
+    let schedule = simulate(5, Policy::Alphabetical, &Dependencies::new(), puzzle_duration).unwrap();
+
+    assert_eq!(schedule.duration, 0);
+    assert_eq!(schedule.assignments, vec![]);
+    assert_eq!(schedule.to_table().lines().count(), 2);
+  }
+
+  #[test]
+  fn test_simulate_with_cycle() {
+    let dependencies = dependencies_from(&[EXAMPLE_RULES, &[("E", "C")]].concat());
+
+    assert_eq!(
+      simulate(2, Policy::Alphabetical, &dependencies, puzzle_duration),
+      Err(ValidationError::Cycle(["A", "B", "E", "C", "A"].iter().map(|step| step.to_string()).collect()))
+    );
+  }
+
+  #[test]
+  fn test_simulate_longest_remaining_path_first() {
+    let dependencies = dependencies_from(&[("A", "B"), ("C", "B"), ("Y", "Z")]);
+
+    // Starting on Y right away keeps the long chain from waiting on A.
+    let alphabetical = simulate(2, Policy::Alphabetical, &dependencies, puzzle_duration).unwrap();
+    let longest_first = simulate(2, Policy::LongestRemainingPath, &dependencies, puzzle_duration).unwrap();
+
+    assert_eq!((join_steps(&alphabetical.order), alphabetical.duration), ("ACBYZ".to_string(), 52));
+    assert_eq!((join_steps(&longest_first.order), longest_first.duration), ("CABYZ".to_string(), 51));
+    assert_eq!(critical::critical_path(&dependencies, puzzle_duration).length, 51);
+  }
+
+  #[test]
+  fn test_simulate_named_steps() {
+    let input = "\
+Step checkout must be finished before step compile can begin.
+Step checkout must be finished before step lint can begin.
+Step compile must be finished before step test can begin.
+Step lint must be finished before step test can begin.
+";
+    let (dependencies, errors) = parse_dependencies(input.as_bytes());
+    let durations: Durations = "checkout 5\ncompile 30\nlint 10\ntest 20\n".parse().unwrap();
+    let schedule = simulate(2, Policy::Alphabetical, &dependencies, |step| durations.get(step).unwrap()).unwrap();
+
+    assert_eq!(errors, vec![]);
+    assert_eq!(join_steps(&schedule.order), "checkout, lint, compile, test");
+    assert_eq!(schedule.duration, 55);
+    assert_eq!(
+      schedule.to_table().lines().take(2).collect::<Vec<_>>(),
+      vec![
+        "Second   Worker 1    Worker 2    Done",
+        "   0        checkout    .",
+      ]
+    );
+    assert_eq!(
+      schedule.to_gantt(80),
+      "\
+Worker 1  AAAAACCCCCCCCCCCCCCCCCCCCCCCCCCCCCCDDDDDDDDDDDDDDDDDDDD
+Worker 2  .....BBBBBBBBBB........................................
+          0         10        20        30        40        50
+A = checkout
+B = lint
+C = compile
+D = test
+"
+    );
+  }
 }
@@ -0,0 +1,184 @@
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+use super::Dependencies;
+
+/// When a step can start at the earliest and at the latest without delaying
+/// everything else, given unlimited workers.
+#[derive(Debug, PartialEq)]
+pub struct StepTiming {
+  pub step: String,
+  pub earliest_start: i32,
+  pub latest_start: i32
+}
+
+impl StepTiming {
+  pub fn slack(&self) -> i32 {
+    self.latest_start - self.earliest_start
+  }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CriticalPath {
+  /// The shortest time all the steps could take, however many workers there
+  /// are.
+  pub length: i32,
+  /// The longest chain of steps, in order.
+  pub path: Vec<String>,
+  /// Every step, in alphabetical order.
+  pub timings: Vec<StepTiming>
+}
+
+/// The steps in the order a single worker would do them, taking the first
+/// available step alphabetically. The dependencies can't have a cycle.
+pub fn topological_order(dependencies: &Dependencies) -> Vec<&str> {
+  let dependents = dependents(dependencies);
+  let mut waiting_on: HashMap<&str, usize> = dependencies.iter()
+    .map(|(step, dependencies)| (step.as_str(), dependencies.len()))
+    .collect();
+  let mut available: BTreeSet<&str> = waiting_on.iter()
+    .filter(|&(_, &n)| n == 0)
+    .map(|(&step, _)| step)
+    .collect();
+  let mut order = Vec::with_capacity(dependencies.len());
+
+  while let Some(&step) = available.iter().next() {
+    available.remove(step);
+    order.push(step);
+
+    for &dependent in dependents[step].iter() {
+      let n = waiting_on.get_mut(dependent).unwrap();
+      *n -= 1;
+      if *n == 0 {
+        available.insert(dependent);
+      }
+    }
+  }
+
+  order
+}
+
+/// Which steps depend on each step.
+pub fn dependents(dependencies: &Dependencies) -> HashMap<&str, HashSet<&str>> {
+  let mut dependents: HashMap<&str, HashSet<&str>> = dependencies.keys().map(|step| (step.as_str(), HashSet::new())).collect();
+
+  for (step, dependencies) in dependencies.iter() {
+    for dependency in dependencies.iter() {
+      dependents.get_mut(dependency.as_str()).unwrap().insert(step);
+    }
+  }
+
+  dependents
+}
+
+/// How long it takes from starting each step until everything that depends
+/// on it is done, counting only the longest chain of dependents.
+pub fn remaining_path_lengths<F: Fn(&str) -> i32>(dependencies: &Dependencies, duration: F) -> HashMap<&str, i32> {
+  let dependents = dependents(dependencies);
+  let mut lengths = HashMap::new();
+
+  for &step in topological_order(dependencies).iter().rev() {
+    let longest_after = dependents[step].iter().map(|dependent| lengths[dependent]).max().unwrap_or(0);
+    lengths.insert(step, duration(step) + longest_after);
+  }
+
+  lengths
+}
+
+/// Works out the critical path through the steps: the chain of steps that
+/// takes the longest, which no number of workers can finish any faster.
+/// The dependencies can't have a cycle.
+pub fn critical_path<F: Fn(&str) -> i32>(dependencies: &Dependencies, duration: F) -> CriticalPath {
+  let order = topological_order(dependencies);
+  let dependents = dependents(dependencies);
+
+  let mut earliest_start: HashMap<&str, i32> = HashMap::new();
+  for &step in order.iter() {
+    let start = dependencies[step].iter()
+      .map(|dependency| earliest_start[dependency.as_str()] + duration(dependency))
+      .max()
+      .unwrap_or(0);
+    earliest_start.insert(step, start);
+  }
+
+  let length = order.iter().map(|&step| earliest_start[step] + duration(step)).max().unwrap_or(0);
+
+  let mut latest_start: HashMap<&str, i32> = HashMap::new();
+  for &step in order.iter().rev() {
+    let finish = dependents[step].iter().map(|dependent| latest_start[dependent]).min().unwrap_or(length);
+    latest_start.insert(step, finish - duration(step));
+  }
+
+  // Follow the steps without slack back from the last one to finish.
+  let mut path = Vec::new();
+  let mut next = order.iter()
+    .filter(|&&step| earliest_start[step] + duration(step) == length)
+    .min()
+    .cloned();
+  while let Some(step) = next {
+    path.push(step.to_string());
+    next = dependencies[step].iter()
+      .map(String::as_str)
+      .filter(|&dependency| earliest_start[dependency] + duration(dependency) == earliest_start[step])
+      .min();
+  }
+  path.reverse();
+
+  let mut timings: Vec<StepTiming> = order.iter()
+    .map(|&step| StepTiming {
+      step: step.to_string(),
+      earliest_start: earliest_start[step],
+      latest_start: latest_start[step]
+    })
+    .collect();
+  timings.sort_by(|a, b| a.step.cmp(&b.step));
+
+  CriticalPath { length, path, timings }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use super::super::{dependencies_from, EXAMPLE_RULES};
+
+  fn duration(step: &str) -> i32 {
+    1 + (step.as_bytes()[0] as i32) - i32::from(b'A')
+  }
+
+  #[test]
+  fn test_topological_order() {
+    assert_eq!(topological_order(&dependencies_from(EXAMPLE_RULES)), vec!["C", "A", "B", "D", "F", "E"]);
+  }
+
+  #[test]
+  fn test_remaining_path_lengths() {
+    let dependencies = dependencies_from(EXAMPLE_RULES);
+    let lengths = remaining_path_lengths(&dependencies, duration);
+
+    assert_eq!(lengths["E"], 5);
+    assert_eq!(lengths["F"], 11);
+    assert_eq!(lengths["A"], 10);
+    assert_eq!(lengths["C"], 14);
+  }
+
+  #[test]
+  fn test_critical_path() {
+    let critical = critical_path(&dependencies_from(EXAMPLE_RULES), duration);
+
+    assert_eq!(critical.length, 14);
+    assert_eq!(critical.path, vec!["C", "F", "E"]);
+    assert_eq!(
+      critical.timings,
+      vec![
+        StepTiming { step: "A".to_string(), earliest_start: 3, latest_start: 4 },
+        StepTiming { step: "B".to_string(), earliest_start: 4, latest_start: 7 },
+        StepTiming { step: "C".to_string(), earliest_start: 0, latest_start: 0 },
+        StepTiming { step: "D".to_string(), earliest_start: 4, latest_start: 5 },
+        StepTiming { step: "E".to_string(), earliest_start: 9, latest_start: 9 },
+        StepTiming { step: "F".to_string(), earliest_start: 3, latest_start: 3 },
+      ]
+    );
+    assert!(critical.path.iter().all(|step| critical.timings.iter().any(|t| t.step == *step && t.slack() == 0)));
+  }
+}
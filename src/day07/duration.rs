@@ -0,0 +1,92 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// How long each step takes.
+#[derive(Debug, PartialEq)]
+pub enum Durations {
+  /// `step_time` seconds plus 1 for A, 2 for B, and so on, like in the
+  /// puzzle. Steps that aren't a single capital letter have no duration.
+  Alphabet { step_time: i32 },
+  /// Read from a file with a step and its duration in seconds on each line,
+  /// and `#` starting a comment.
+  Table(HashMap<String, i32>)
+}
+
+impl Durations {
+  pub fn get(&self, step: &str) -> Option<i32> {
+    match self {
+      Durations::Alphabet { step_time } => {
+        let mut chars = step.chars();
+        match (chars.next(), chars.next()) {
+          (Some(letter), None) if letter.is_ascii_uppercase() =>
+            Some(step_time + 1 + (letter as i32) - i32::from(b'A')),
+          _ => None
+        }
+      },
+      Durations::Table(durations) => durations.get(step).cloned()
+    }
+  }
+}
+
+impl FromStr for Durations {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, String> {
+    let mut durations = HashMap::new();
+
+    for (index, line) in s.lines().enumerate() {
+      let fields: Vec<&str> = line.split('#').next().unwrap().split_whitespace().collect();
+
+      match fields[..] {
+        [] => {},
+        [step, seconds] => {
+          let seconds: i32 = seconds.parse()
+            .map_err(|_| format!("line {}: invalid duration {:?}", index + 1, seconds))?;
+          if seconds <= 0 {
+            return Err(format!("line {}: durations have to be positive", index + 1));
+          }
+          durations.insert(step.to_string(), seconds);
+        },
+        _ => return Err(format!("line {}: expected a step and a duration", index + 1))
+      }
+    }
+
+    Ok(Durations::Table(durations))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_alphabet_durations() {
+    let durations = Durations::Alphabet { step_time: 60 };
+
+    assert_eq!(durations.get("A"), Some(61));
+    assert_eq!(durations.get("Z"), Some(86));
+    assert_eq!(durations.get("build"), None);
+  }
+
+  #[test]
+  fn test_durations_from_str() {
+    let durations: Durations = "\
+# Build pipeline
+checkout      5
+compile-core  120   # the slow one
+
+test 45
+".parse().unwrap();
+
+    assert_eq!(durations.get("compile-core"), Some(120));
+    assert_eq!(durations.get("test"), Some(45));
+    assert_eq!(durations.get("deploy"), None);
+  }
+
+  #[test]
+  fn test_durations_from_str_errors() {
+    assert_eq!("a 1\nb\n".parse::<Durations>(), Err("line 2: expected a step and a duration".to_string()));
+    assert_eq!("a x\n".parse::<Durations>(), Err("line 1: invalid duration \"x\"".to_string()));
+    assert!("a 0\n".parse::<Durations>().is_err());
+  }
+}
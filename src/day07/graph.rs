@@ -0,0 +1,136 @@
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+use super::critical::topological_order;
+use super::schedule::Schedule;
+use super::Dependencies;
+
+/// Drops every dependency that's implied by the others: if C has to wait
+/// for B, and B for A, then C doesn't need its own arrow from A. The
+/// dependencies can't have a cycle.
+pub fn transitive_reduction(dependencies: &Dependencies) -> Dependencies {
+  // Everything each step (indirectly) waits for.
+  let mut ancestors: HashMap<&str, HashSet<&str>> = HashMap::new();
+  for step in topological_order(dependencies) {
+    let mut all = HashSet::new();
+    for dependency in dependencies[step].iter() {
+      all.insert(dependency.as_str());
+      all.extend(ancestors[dependency.as_str()].iter().cloned());
+    }
+    ancestors.insert(step, all);
+  }
+
+  dependencies.iter()
+    .map(|(step, direct)| {
+      let needed = direct.iter()
+        .filter(|&dependency| !direct.iter().any(|other| ancestors[other.as_str()].contains(dependency.as_str())))
+        .cloned()
+        .collect();
+      (step.clone(), needed)
+    })
+    .collect()
+}
+
+fn escape(step: &str) -> String {
+  step.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn quote(step: &str) -> String {
+  format!("\"{}\"", escape(step))
+}
+
+/// Renders the steps as a Graphviz digraph with an arrow from each step to
+/// the ones waiting for it. With a schedule, each step is labelled with when
+/// it finished relative to the others and the seconds it ran.
+pub fn to_dot(dependencies: &Dependencies, schedule: Option<&Schedule>) -> String {
+  let mut steps: Vec<&String> = dependencies.keys().collect();
+  steps.sort();
+
+  let mut dot = String::from("digraph steps {\n");
+
+  if let Some(schedule) = schedule {
+    for &step in steps.iter() {
+      let position = schedule.order.iter().position(|done| done == step).unwrap();
+      let assignment = schedule.assignments.iter().find(|a| a.step == *step).unwrap();
+      dot.push_str(&format!(
+        "  {} [label=\"{}\\n#{}, {}-{}s\"];\n",
+        quote(step),
+        escape(step),
+        position + 1,
+        assignment.start,
+        assignment.end
+      ));
+    }
+  }
+
+  for &step in steps.iter() {
+    let mut step_dependencies: Vec<&String> = dependencies[step].iter().collect();
+    step_dependencies.sort();
+
+    if schedule.is_none() && step_dependencies.is_empty() && !dependencies.values().any(|d| d.contains(step)) {
+      dot.push_str(&format!("  {};\n", quote(step)));
+    }
+    for dependency in step_dependencies {
+      dot.push_str(&format!("  {} -> {};\n", quote(dependency), quote(step)));
+    }
+  }
+
+  dot.push_str("}\n");
+  dot
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use super::super::dependencies_from;
+  use super::super::schedule::Assignment;
+
+  #[test]
+  fn test_transitive_reduction() {
+    let reduced = transitive_reduction(&dependencies_from(&[("A", "B"), ("B", "C"), ("A", "C"), ("C", "D"), ("A", "D"), ("X", "D")]));
+
+    assert_eq!(reduced, dependencies_from(&[("A", "B"), ("B", "C"), ("C", "D"), ("X", "D")]));
+  }
+
+  #[test]
+  fn test_to_dot() {
+    let mut dependencies = dependencies_from(&[("A", "B"), ("A", "C")]);
+    dependencies.insert("lone \"step\"".to_string(), HashSet::new());
+
+    assert_eq!(
+      to_dot(&dependencies, None),
+      "\
+digraph steps {
+  \"A\" -> \"B\";
+  \"A\" -> \"C\";
+  \"lone \\\"step\\\"\";
+}
+"
+    );
+  }
+
+  #[test]
+  fn test_to_dot_with_schedule() {
+    let dependencies = dependencies_from(&[("A", "B")]);
+    let schedule = Schedule {
+      num_workers: 1,
+      assignments: vec![
+        Assignment { worker: 0, step: "A".to_string(), start: 0, end: 1 },
+        Assignment { worker: 0, step: "B".to_string(), start: 1, end: 3 },
+      ],
+      order: vec!["A".to_string(), "B".to_string()],
+      duration: 3
+    };
+
+    assert_eq!(
+      to_dot(&dependencies, Some(&schedule)),
+      "\
+digraph steps {
+  \"A\" [label=\"A\\n#1, 0-1s\"];
+  \"B\" [label=\"B\\n#2, 1-3s\"];
+  \"A\" -> \"B\";
+}
+"
+    );
+  }
+}
@@ -0,0 +1,117 @@
+use super::join_steps;
+
+/// A worker working on a step from `start` until just before `end`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Assignment {
+  pub worker: usize,
+  pub step: String,
+  pub start: i32,
+  pub end: i32
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Schedule {
+  pub num_workers: usize,
+  /// In the order the steps were started.
+  pub assignments: Vec<Assignment>,
+  /// The steps in the order they were finished.
+  pub order: Vec<String>,
+  pub duration: i32
+}
+
+impl Schedule {
+  // The step a worker is on during a second, if any.
+  fn step_at(&self, worker: usize, second: i32) -> Option<&str> {
+    self.assignments.iter()
+      .find(|a| a.worker == worker && a.start <= second && second < a.end)
+      .map(|a| a.step.as_str())
+  }
+
+  /// Renders the schedule like the puzzle does: a row for every second up
+  /// to the one where everything's done, showing what each worker is doing
+  /// and which steps are finished.
+  pub fn to_table(&self) -> String {
+    // Wide enough for "Worker N" and the longest step name.
+    let longest_step = self.order.iter().map(String::len).max().unwrap_or(1);
+    let cell_width = 11.max(longest_step + 4);
+
+    let mut table = String::from("Second   ");
+    for worker in 0..self.num_workers {
+      table.push_str(&format!("{:<width$}", format!("Worker {}", worker + 1), width = cell_width));
+    }
+    table.push_str("Done\n");
+
+    let mut finished: Vec<&Assignment> = self.assignments.iter().collect();
+    finished.sort_by_key(|a| (a.end, self.order.iter().position(|step| *step == a.step)));
+
+    for second in 0..=self.duration {
+      let mut row = format!("{:>4}     ", second);
+      for worker in 0..self.num_workers {
+        let step = self.step_at(worker, second).unwrap_or(".");
+        row.push_str(&format!("   {:<width$}", step, width = cell_width - 3));
+      }
+
+      let done: Vec<String> = finished.iter().filter(|a| a.end <= second).map(|a| a.step.clone()).collect();
+      row.push_str(&join_steps(&done));
+
+      table.push_str(row.trim_end());
+      table.push('\n');
+    }
+
+    table
+  }
+
+  // A different character for each step, in the order they were finished.
+  // Steps named with a single character are drawn as themselves, as in the
+  // puzzle. Otherwise they're lettered, and the letters need a legend.
+  fn symbols(&self) -> (Vec<(char, &str)>, bool) {
+    let single = |step: &String| step.chars().count() == 1;
+
+    if self.order.iter().all(single) {
+      let symbols = self.order.iter().map(|step| (step.chars().next().unwrap(), step.as_str())).collect();
+      return (symbols, false);
+    }
+
+    let letters = ('A'..='Z').chain('a'..='z').chain('0'..='9').chain((0xc0..).filter_map(std::char::from_u32));
+    (letters.zip(self.order.iter().map(String::as_str)).collect(), true)
+  }
+
+  /// Renders a bar for each worker, `width` columns wide at most, with each
+  /// column standing for the same number of seconds. A column shows the
+  /// step the worker is on when it starts, or `.` if the worker is idle.
+  /// Steps with longer names are shown as letters, listed under the chart.
+  pub fn to_gantt(&self, width: usize) -> String {
+    let (symbols, needs_legend) = self.symbols();
+    let symbol = |step: &str| symbols.iter().find(|&&(_, s)| s == step).map_or('?', |&(symbol, _)| symbol);
+
+    let seconds_per_column = (self.duration.max(1) as usize).div_ceil(width.max(1)) as i32;
+    let columns = (self.duration + seconds_per_column - 1) / seconds_per_column;
+    let label_width = format!("Worker {}", self.num_workers).len();
+
+    let mut gantt = String::new();
+    if seconds_per_column > 1 {
+      gantt.push_str(&format!("{} seconds per column\n", seconds_per_column));
+    }
+
+    for worker in 0..self.num_workers {
+      let bar: String = (0..columns)
+        .map(|column| self.step_at(worker, column * seconds_per_column).map_or('.', symbol))
+        .collect();
+      gantt.push_str(&format!("{:<width$}  {}\n", format!("Worker {}", worker + 1), bar, width = label_width));
+    }
+
+    let mut axis = String::new();
+    for column in (0..=columns).step_by(10) {
+      axis.push_str(&format!("{:<10}", column * seconds_per_column));
+    }
+    gantt.push_str(&format!("{:width$}  {}\n", "", axis.trim_end(), width = label_width));
+
+    if needs_legend {
+      for (symbol, step) in symbols {
+        gantt.push_str(&format!("{} = {}\n", symbol, step));
+      }
+    }
+
+    gantt
+  }
+}
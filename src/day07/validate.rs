@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use super::Dependencies;
+
+#[derive(Debug, PartialEq)]
+pub enum ValidationError {
+  Unparseable { line: usize, text: String },
+  /// Steps that each have to be finished before the next one, ending with
+  /// the step they started with.
+  Cycle(Vec<String>),
+  MissingDuration(String)
+}
+
+impl fmt::Display for ValidationError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      ValidationError::Unparseable { line, text } =>
+        write!(f, "line {}: not a step dependency: {:?}", line, text),
+      ValidationError::Cycle(steps) =>
+        write!(f, "dependency cycle: {}", steps.join(" -> ")),
+      ValidationError::MissingDuration(step) =>
+        write!(f, "no duration for step {}", step)
+    }
+  }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Visit {
+  InProgress,
+  Done
+}
+
+/// Finds steps that can never start because they (indirectly) depend on
+/// themselves, and returns them in the order they'd have to be finished.
+pub fn find_cycle(dependencies: &Dependencies) -> Option<Vec<String>> {
+  let mut steps: Vec<&str> = dependencies.keys().map(String::as_str).collect();
+  steps.sort();
+
+  let mut visits = HashMap::new();
+  let mut path = Vec::new();
+
+  steps.into_iter().find_map(|step| visit(step, dependencies, &mut visits, &mut path))
+}
+
+// Depth first search through the dependencies, keeping the path to the
+// current step so a cycle can be cut out of it when the search comes back
+// around to a step that's still in progress.
+fn visit<'a>(
+  step: &'a str,
+  dependencies: &'a Dependencies,
+  visits: &mut HashMap<&'a str, Visit>,
+  path: &mut Vec<&'a str>
+) -> Option<Vec<String>> {
+  match visits.get(step) {
+    Some(Visit::Done) => return None,
+    Some(Visit::InProgress) => {
+      let start = path.iter().position(|&s| s == step).unwrap();
+      let mut cycle: Vec<String> = path[start..].iter().map(|s| s.to_string()).collect();
+      cycle.push(step.to_string());
+      cycle.reverse();
+      return Some(cycle);
+    },
+    None => {}
+  }
+
+  visits.insert(step, Visit::InProgress);
+  path.push(step);
+
+  let mut next: Vec<&str> = dependencies.get(step).map_or(Vec::new(), |d| d.iter().map(String::as_str).collect());
+  next.sort();
+  for dependency in next {
+    if let Some(cycle) = visit(dependency, dependencies, visits, path) {
+      return Some(cycle);
+    }
+  }
+
+  path.pop();
+  visits.insert(step, Visit::Done);
+  None
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use super::super::{dependencies_from, EXAMPLE_RULES};
+
+  fn steps(steps: &[&str]) -> Vec<String> {
+    steps.iter().map(|step| step.to_string()).collect()
+  }
+
+  #[test]
+  fn test_find_cycle_without_cycle() {
+    let dependencies = dependencies_from(EXAMPLE_RULES);
+
+    assert_eq!(find_cycle(&dependencies), None);
+  }
+
+  #[test]
+  fn test_find_cycle() {
+    let dependencies = dependencies_from(&[("A", "B"), ("B", "C"), ("C", "D"), ("D", "B"), ("X", "A")]);
+
+    assert_eq!(find_cycle(&dependencies), Some(steps(&["B", "C", "D", "B"])));
+    assert_eq!(find_cycle(&dependencies_from(&[("A", "A")])), Some(steps(&["A", "A"])));
+  }
+
+  #[test]
+  fn test_display() {
+    assert_eq!(
+      ValidationError::Cycle(steps(&["B", "C", "B"])).to_string(),
+      "dependency cycle: B -> C -> B"
+    );
+    assert_eq!(
+      ValidationError::Unparseable { line: 3, text: "Step 1".to_string() }.to_string(),
+      "line 3: not a step dependency: \"Step 1\""
+    );
+    assert_eq!(
+      ValidationError::MissingDuration("deploy".to_string()).to_string(),
+      "no duration for step deploy"
+    );
+  }
+}
@@ -1,7 +1,12 @@
 use std::fs::File;
 use std::io::BufReader;
 use std::io::prelude::*;
+use std::mem;
 use std::str::FromStr;
+use crate::options;
+
+mod iterative;
+mod render;
 
 #[derive(Debug, PartialEq)]
 struct Node {
@@ -21,7 +26,7 @@ impl Node {
   }
 
   pub fn from_ints_aux(ints: &[i32]) -> Result<(Node, &[i32]), &'static str> {
-    let num_children = *ints.get(0).ok_or("expected num children")? as usize;
+    let num_children = *ints.first().ok_or("expected num children")? as usize;
     let num_metadata = *ints.get(1).ok_or("expected num metadata")? as usize;
     let mut ints = &ints[2..];
 
@@ -55,40 +60,102 @@ impl Node {
     self_sum + children_sum
   }
 
+  // The value of a node with children, given each child's value.
+  fn value_from_children(&self, child_values: &[i32]) -> i32 {
+    self.metadata.iter()
+      .filter_map(|&index| (index as usize).checked_sub(1))
+      .filter_map(|index| child_values.get(index))
+      .sum()
+  }
+
   pub fn value(&self) -> i32 {
     if self.children.is_empty() {
       self.metadata.iter().sum()
     } else {
       self.metadata.iter()
-        .filter_map(|&index| self.children.get(index as usize - 1))
+        .filter_map(|&index| (index as usize).checked_sub(1))
+        .filter_map(|index| self.children.get(index))
         .map(Node::value)
         .sum()
     }
   }
 }
 
+// Dropping children one level at a time would recurse as deep as the tree,
+// so move them all onto a stack first.
+impl Drop for Node {
+  fn drop(&mut self) {
+    let mut stack = mem::take(&mut self.children);
+    while let Some(mut node) = stack.pop() {
+      stack.append(&mut node.children);
+    }
+  }
+}
+
+fn parse_ints(string: &str) -> Result<Vec<i32>, &'static str> {
+  string.split_whitespace()
+    .map(i32::from_str)
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|_| "string contains invalid numbers")
+}
+
 impl FromStr for Node {
   type Err = &'static str;
 
   fn from_str(string: &str) -> Result<Node, Self::Err> {
-    string.split_whitespace()
-      .map(i32::from_str)
-      .collect::<Result<Vec<_>, _>>()
-      .map_err(|_| "string contains invalid numbers")
-      .and_then(|ints| Node::from_ints(&ints))
+    parse_ints(string).and_then(|ints| Node::from_ints(&ints))
   }
 }
 
-pub fn solve(input_file: File) {
+pub fn solve(input_file: File, options: &[String]) {
+  let mut max_depth = None;
+  let mut recursive = false;
+  let mut show_ints = false;
+  let mut show_tree = false;
+  let mut show_dot = false;
+
+  for option in options.iter() {
+    match options::split(option) {
+      ("max-depth", Some(n)) => max_depth = Some(n.parse().expect("--max-depth takes a number")),
+      ("recursive", None) => recursive = true,
+      ("ints", None) => show_ints = true,
+      ("tree", None) => show_tree = true,
+      ("dot", None) => show_dot = true,
+      _ => panic!("Unknown option for day 8: {}", option)
+    }
+  }
+
   let mut reader = BufReader::new(input_file);
 
   let mut input = String::new();
   reader.read_to_string(&mut input).unwrap();
 
-  let tree: Node = input.parse().unwrap();
+  let tree = if recursive {
+    input.parse()
+  } else {
+    parse_ints(&input).and_then(|ints| Node::from_ints_iterative(&ints, max_depth))
+  }.unwrap_or_else(|error| panic!("Invalid license file: {}", error));
+
+  if recursive {
+    println!("Sum of metadata: {}", tree.metadata_sum());
+    println!("Value of tree: {}", tree.value());
+  } else {
+    println!("Sum of metadata: {}", tree.metadata_sum_iterative());
+    println!("Value of tree: {}", tree.value_iterative());
+  }
 
-  println!("Sum of metadata: {}", tree.metadata_sum());
-  println!("Value of tree: {}", tree.value());
+  if show_ints {
+    println!();
+    println!("{}", tree);
+  }
+  if show_tree {
+    println!();
+    print!("{}", tree.to_tree());
+  }
+  if show_dot {
+    println!();
+    print!("{}", tree.to_dot());
+  }
 }
 
 #[cfg(test)]
@@ -0,0 +1,180 @@
+use super::Node;
+
+// A node whose header has been read but whose children are still coming.
+struct Partial {
+  num_children: usize,
+  num_metadata: usize,
+  children: Vec<Node>
+}
+
+// A node and the values of the children worked out so far.
+struct Evaluating<'a> {
+  node: &'a Node,
+  child_values: Vec<i32>
+}
+
+impl Node {
+  /// Does the same as `from_ints`, keeping the nodes still being read on an
+  /// explicit stack instead of recursing, so deep trees can't overflow the
+  /// call stack. Fails if nodes are nested more than `max_depth` deep,
+  /// counting the root as 1.
+  pub fn from_ints_iterative(ints: &[i32], max_depth: Option<usize>) -> Result<Node, &'static str> {
+    let mut stack: Vec<Partial> = Vec::new();
+    let mut ints = ints;
+
+    loop {
+      let num_children = *ints.first().ok_or("expected num children")? as usize;
+      let num_metadata = *ints.get(1).ok_or("expected num metadata")? as usize;
+      ints = &ints[2..];
+
+      if max_depth.is_some_and(|max_depth| stack.len() >= max_depth) {
+        return Err("nodes are nested deeper than the maximum depth");
+      }
+      stack.push(Partial { num_children, num_metadata, children: Vec::new() });
+
+      // Finish every node that has all its children now, innermost first.
+      while stack.last().is_some_and(|partial| partial.children.len() == partial.num_children) {
+        let partial = stack.pop().unwrap();
+
+        if ints.len() < partial.num_metadata {
+          return Err("expected metadata");
+        }
+
+        let node = Node {
+          children: partial.children,
+          metadata: ints[..partial.num_metadata].to_vec(),
+        };
+        ints = &ints[partial.num_metadata..];
+
+        match stack.last_mut() {
+          Some(parent) => parent.children.push(node),
+          None if ints.is_empty() => return Ok(node),
+          None => return Err("excess data")
+        }
+      }
+    }
+  }
+
+  /// Does the same as `metadata_sum` without recursing.
+  pub fn metadata_sum_iterative(&self) -> i32 {
+    let mut sum = 0;
+    let mut stack = vec![self];
+
+    while let Some(node) = stack.pop() {
+      sum += node.metadata.iter().sum::<i32>();
+      stack.extend(node.children.iter());
+    }
+
+    sum
+  }
+
+  /// Does the same as `value` without recursing, working out each child's
+  /// value once however many times it's referred to.
+  pub fn value_iterative(&self) -> i32 {
+    let mut stack = vec![Evaluating { node: self, child_values: Vec::new() }];
+
+    loop {
+      let top = stack.last().unwrap();
+      let node = top.node;
+
+      if top.child_values.len() < node.children.len() {
+        let child = &node.children[top.child_values.len()];
+        stack.push(Evaluating { node: child, child_values: Vec::new() });
+        continue;
+      }
+
+      let evaluated = stack.pop().unwrap();
+      let value = if node.children.is_empty() {
+        node.metadata.iter().sum()
+      } else {
+        node.value_from_children(&evaluated.child_values)
+      };
+
+      match stack.last_mut() {
+        Some(parent) => parent.child_values.push(value),
+        None => return value
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const EXAMPLE: [i32; 16] = [2, 3, 0, 3, 10, 11, 12, 1, 1, 0, 1, 99, 2, 1, 1, 2];
+
+  // A chain of `depth` nodes, each with one child and a metadata entry of 1
+  // pointing at it, ending in a leaf with metadata 7.
+  fn chain(depth: usize) -> Vec<i32> {
+    let mut ints = Vec::new();
+    for _ in 1..depth {
+      ints.extend(&[1, 1]);
+    }
+    ints.extend(&[0, 1, 7]);
+    ints.extend(vec![1; depth - 1]);
+    ints
+  }
+
+  fn next_random(seed: &mut u32, n: u32) -> i32 {
+    *seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+    ((*seed >> 16) % n) as i32
+  }
+
+  // A pseudo-random but valid tree, written out as ints. Some metadata
+  // entries point at children that don't exist.
+  fn random_tree(seed: &mut u32, depth: usize, ints: &mut Vec<i32>) {
+    let num_children = if depth == 0 { 0 } else { next_random(seed, 4) };
+    let num_metadata = 1 + next_random(seed, 3);
+
+    ints.extend(&[num_children, num_metadata]);
+    for _ in 0..num_children {
+      random_tree(seed, depth - 1, ints);
+    }
+    for _ in 0..num_metadata {
+      ints.push(next_random(seed, 6));
+    }
+  }
+
+  #[test]
+  fn from_ints_iterative_matches_recursive() {
+    assert_eq!(Node::from_ints_iterative(&EXAMPLE, None), Node::from_ints(&EXAMPLE));
+
+    for len in 0..EXAMPLE.len() + 2 {
+      let mut ints = EXAMPLE.to_vec();
+      ints.resize(len, 1);
+      assert_eq!(Node::from_ints_iterative(&ints, None), Node::from_ints(&ints), "{:?}", ints);
+    }
+  }
+
+  #[test]
+  fn evaluations_match_recursive() {
+    let mut seed = 1;
+    for _ in 0..50 {
+      let mut ints = Vec::new();
+      random_tree(&mut seed, 5, &mut ints);
+      let tree = Node::from_ints_iterative(&ints, None).unwrap();
+
+      assert_eq!(tree, Node::from_ints(&ints).unwrap());
+      assert_eq!(tree.metadata_sum_iterative(), tree.metadata_sum());
+      assert_eq!(tree.value_iterative(), tree.value());
+    }
+  }
+
+  #[test]
+  fn deep_tree() {
+    let tree = Node::from_ints_iterative(&chain(200_000), None).unwrap();
+
+    assert_eq!(tree.metadata_sum_iterative(), 7 + 199_999);
+    assert_eq!(tree.value_iterative(), 7);
+  }
+
+  #[test]
+  fn max_depth() {
+    assert!(Node::from_ints_iterative(&chain(3), Some(3)).is_ok());
+    assert_eq!(
+      Node::from_ints_iterative(&chain(4), Some(3)),
+      Err("nodes are nested deeper than the maximum depth")
+    );
+  }
+}
@@ -0,0 +1,159 @@
+use std::fmt;
+
+use super::Node;
+
+// A node along with where it sits in the tree and its value.
+struct Annotated<'a> {
+  node: &'a Node,
+  depth: usize,
+  parent: Option<usize>,
+  value: i32
+}
+
+impl Node {
+  /// Writes the tree back out in the puzzle's encoding: a node's child
+  /// count and metadata count, then its children, then its metadata.
+  pub fn to_ints(&self) -> Vec<i32> {
+    enum Next<'a> {
+      Node(&'a Node),
+      Metadata(&'a Node)
+    }
+
+    let mut ints = Vec::new();
+    let mut stack = vec![Next::Node(self)];
+
+    while let Some(next) = stack.pop() {
+      match next {
+        Next::Node(node) => {
+          ints.push(node.children.len() as i32);
+          ints.push(node.metadata.len() as i32);
+          stack.push(Next::Metadata(node));
+          stack.extend(node.children.iter().rev().map(Next::Node));
+        },
+        Next::Metadata(node) => ints.extend(&node.metadata)
+      }
+    }
+
+    ints
+  }
+
+  // Every node in the order they're written out, parents before children,
+  // with their values worked out without recursing.
+  fn annotated(&self) -> Vec<Annotated<'_>> {
+    let mut annotated = vec![Annotated { node: self, depth: 0, parent: None, value: 0 }];
+    // Indexes into `annotated` of the nodes being evaluated, and the values
+    // of their children so far.
+    let mut stack: Vec<(usize, Vec<i32>)> = vec![(0, Vec::new())];
+
+    while let Some((index, child_values)) = stack.last() {
+      let index = *index;
+      let node = annotated[index].node;
+
+      if child_values.len() < node.children.len() {
+        let child = &node.children[child_values.len()];
+        annotated.push(Annotated { node: child, depth: annotated[index].depth + 1, parent: Some(index), value: 0 });
+        stack.push((annotated.len() - 1, Vec::new()));
+        continue;
+      }
+
+      let (_, child_values) = stack.pop().unwrap();
+      let value = if node.children.is_empty() {
+        node.metadata.iter().sum()
+      } else {
+        node.value_from_children(&child_values)
+      };
+      annotated[index].value = value;
+
+      if let Some((_, parent_values)) = stack.last_mut() {
+        parent_values.push(value);
+      }
+    }
+
+    annotated
+  }
+
+  /// One line per node, indented by depth, with its metadata and value.
+  pub fn to_tree(&self) -> String {
+    self.annotated().iter()
+      .map(|a| format!("{}metadata {:?}, value {}\n", "  ".repeat(a.depth), a.node.metadata, a.value))
+      .collect()
+  }
+
+  /// Renders the tree as a Graphviz digraph, labelling each node with its
+  /// metadata and value.
+  pub fn to_dot(&self) -> String {
+    let annotated = self.annotated();
+    let mut dot = String::from("digraph tree {\n");
+
+    for (index, a) in annotated.iter().enumerate() {
+      let metadata: Vec<String> = a.node.metadata.iter().map(i32::to_string).collect();
+      dot.push_str(&format!("  n{} [label=\"{}\\nvalue {}\"];\n", index, metadata.join(" "), a.value));
+    }
+    for (index, a) in annotated.iter().enumerate() {
+      if let Some(parent) = a.parent {
+        dot.push_str(&format!("  n{} -> n{};\n", parent, index));
+      }
+    }
+
+    dot.push_str("}\n");
+    dot
+  }
+}
+
+impl fmt::Display for Node {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let ints: Vec<String> = self.to_ints().iter().map(i32::to_string).collect();
+    write!(f, "{}", ints.join(" "))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const EXAMPLE: &str = "2 3 0 3 10 11 12 1 1 0 1 99 2 1 1 2";
+
+  #[test]
+  fn node_round_trip() {
+    let tree: Node = EXAMPLE.parse().unwrap();
+
+    assert_eq!(tree.to_string(), EXAMPLE);
+    assert_eq!(tree.to_string().parse::<Node>().unwrap(), tree);
+    assert_eq!(tree.to_ints(), Node::from_ints(&tree.to_ints()).unwrap().to_ints());
+  }
+
+  #[test]
+  fn node_to_tree() {
+    let tree: Node = EXAMPLE.parse().unwrap();
+
+    assert_eq!(
+      tree.to_tree(),
+      "\
+metadata [1, 1, 2], value 66
+  metadata [10, 11, 12], value 33
+  metadata [2], value 0
+    metadata [99], value 99
+"
+    );
+  }
+
+  #[test]
+  fn node_to_dot() {
+    let tree: Node = EXAMPLE.parse().unwrap();
+
+    assert_eq!(
+      tree.to_dot(),
+      "\
+digraph tree {
+  n0 [label=\"1 1 2\\nvalue 66\"];
+  n1 [label=\"10 11 12\\nvalue 33\"];
+  n2 [label=\"2\\nvalue 0\"];
+  n3 [label=\"99\\nvalue 99\"];
+  n0 -> n1;
+  n0 -> n2;
+  n2 -> n3;
+}
+"
+    );
+  }
+}
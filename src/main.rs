@@ -22,32 +22,42 @@ mod day16;
 mod day17;
 mod day18;
 mod day19;
+mod options;
 
 fn main() -> io::Result<()> {
   let args: Vec<String> = env::args().collect();
 
-  if args.len() < 2 || args.len() > 3 {
-    println!("Usage: {} <day number> [input file]", &args[0]);
+  let (options, paths): (Vec<String>, Vec<String>) = args.iter()
+    .skip(2)
+    .cloned()
+    .partition(|arg| arg.starts_with("--"));
+
+  if args.len() < 2 || paths.len() > 1 {
+    println!("Usage: {} <day number> [input file] [--option...]", &args[0]);
     process::exit(1);
   }
 
   let day_number: u8 = args[1].parse()
     .expect("first argument must be a number");
 
-  let file = match args.len() {
-    3 => File::open(&args[2]),
-    _ => File::open(format!("input/input{:02}", day_number))
+  if !options.is_empty() && !(3..=8).contains(&day_number) {
+    panic!("Day {} doesn't take any options, got: {}", day_number, options.join(" "));
+  }
+
+  let file = match paths.first() {
+    Some(path) => File::open(path),
+    None => File::open(format!("input/input{:02}", day_number))
   }.expect("input file doesn't exist");
 
   match day_number {
     1 => day01::solve(file),
     2 => day02::solve(file),
-    3 => day03::solve(file),
-    4 => day04::solve(file),
-    5 => day05::solve(file),
-    6 => day06::solve(file),
-    7 => day07::solve(file),
-    8 => day08::solve(file),
+    3 => day03::solve(file, &options),
+    4 => day04::solve(file, &options),
+    5 => day05::solve(file, &options),
+    6 => day06::solve(file, &options),
+    7 => day07::solve(file, &options),
+    8 => day08::solve(file, &options),
     9 => day09::solve(file),
     10 => day10::solve(file),
     11 => day11::solve(file),
@@ -0,0 +1,22 @@
+/// Splits a command line option of the form `--name` or `--name=value` into
+/// its name and optional value.
+pub fn split(option: &str) -> (&str, Option<&str>) {
+  let option = option.trim_start_matches("--");
+
+  match option.find('=') {
+    Some(index) => (&option[..index], Some(&option[index + 1..])),
+    None => (option, None)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_split() {
+    assert_eq!(split("--dot"), ("dot", None));
+    assert_eq!(split("--heatmap=fabric.pgm"), ("heatmap", Some("fabric.pgm")));
+    assert_eq!(split("--top=3=4"), ("top", Some("3=4")));
+  }
+}